@@ -0,0 +1,386 @@
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use crate::commands::{bind_local, gen_id, parse_command};
+use crate::connection::{Client, ConnectOptions};
+use crate::flags::{parse_args, Flags, Source, SETTINGS};
+use crate::launch::prepare_daemon;
+use crate::output::{print_connection_error, print_error, print_parse_error, print_response};
+
+/// Split a command line into arguments, shell style.
+///
+/// Whitespace separates arguments. Single quotes keep their contents literally,
+/// double quotes allow `\"` and `\\` escapes, and a backslash outside quotes
+/// escapes the next character.
+pub fn split_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(ch) => current.push(ch),
+                        None => return Err("Unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(ch @ ('"' | '\\')) => current.push(ch),
+                            Some(ch) => {
+                                current.push('\\');
+                                current.push(ch);
+                            }
+                            None => return Err("Unterminated double quote".to_string()),
+                        },
+                        Some(ch) => current.push(ch),
+                        None => return Err("Unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                in_arg = true;
+                if let Some(ch) = chars.next() {
+                    current.push(ch);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Error produced while turning one batch line into a request
+pub enum LineError {
+    Parse(crate::commands::ParseError),
+    Invalid(String),
+}
+
+/// Settings a line may set for itself; the others (`--headed`, `--session`, ...)
+/// only take effect when the session starts or connects
+const LINE_SETTINGS: &[&str] = &["timeout", "headers"];
+
+/// Turn one batch line into a request.
+///
+/// Lines starting with `{` are taken as raw JSON requests (an `id` is added if
+/// missing); anything else is parsed like CLI arguments. Blank lines and
+/// `#` comments yield `None`.
+pub fn parse_line(line: &str, flags: &Flags) -> Result<Option<Value>, LineError> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    if line.starts_with('{') {
+        let mut cmd: Value = serde_json::from_str(line)
+            .map_err(|e| LineError::Invalid(format!("Invalid JSON request: {}", e)))?;
+        let obj = cmd
+            .as_object_mut()
+            .ok_or_else(|| LineError::Invalid("JSON request must be an object".to_string()))?;
+        if !obj.get("action").is_some_and(|a| a.is_string()) {
+            return Err(LineError::Invalid(
+                "JSON request is missing an \"action\"".to_string(),
+            ));
+        }
+        if !obj.get("id").is_some_and(|id| id.is_string()) {
+            obj.insert("id".to_string(), json!(gen_id()));
+        }
+        return Ok(Some(cmd));
+    }
+
+    let tokens = split_line(line).map_err(LineError::Invalid)?;
+    // Per-line flags (e.g. `screenshot --full`) apply to that line only; the
+    // session and output mode always come from the batch invocation.
+    let (mut line_flags, line_args) = parse_args(&tokens).map_err(LineError::Parse)?;
+    for key in SETTINGS {
+        if let Source::Flag { flag } = line_flags.source(key) {
+            if !LINE_SETTINGS.contains(key) {
+                return Err(LineError::Invalid(format!(
+                    "{} can't be set per line; give it to 'batch' or 'shell' itself",
+                    flag
+                )));
+            }
+        }
+    }
+    line_flags.session = flags.session.clone();
+    line_flags.json = flags.json;
+
+    let mut cmd = parse_command(&line_args, &line_flags).map_err(LineError::Parse)?;
+    // A per-line --timeout overrides the batch-wide one
+    if let (Some(ms), Some(obj)) = (line_flags.timeout, cmd.as_object_mut()) {
+        obj.entry("timeout").or_insert(json!(ms));
+    }
+    Ok(Some(cmd))
+}
+
+/// Run `agent-browser batch [file] [--bail]`.
+///
+/// Reads one command per line from `file` (or stdin when omitted or `-`), sends
+/// each to the session over a single connection and prints one response per
+/// line. Returns the process exit code: 0 if every command succeeded, 1 otherwise.
+pub fn run_batch(args: &[String], flags: &Flags) -> i32 {
+    let inv = match bind_local(args) {
+        Ok(inv) if inv.args.len() > 1 => {
+            print_parse_error(
+                &inv.invalid(format!("Unexpected argument: {}", inv.args[1])),
+                flags.json,
+            );
+            return 1;
+        }
+        Ok(inv) => inv,
+        Err(e) => {
+            print_parse_error(&e, flags.json);
+            return 1;
+        }
+    };
+    let bail = inv.has("--bail");
+
+    let reader: Box<dyn BufRead> = match inv.arg(0) {
+        None | Some("-") => Box::new(BufReader::new(io::stdin())),
+        Some(path) => match File::open(path) {
+            Ok(f) => Box::new(BufReader::new(f)),
+            Err(e) => {
+                print_error(&format!("Failed to open {}: {}", path, e), flags.json);
+                return 1;
+            }
+        },
+    };
+
+    if let Err(e) = prepare_daemon(flags) {
+        print_error(&e, flags.json);
+        return 1;
+    }
+
+    let mut client: Option<Client> = None;
+    let mut failed = false;
+
+    for line in reader.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(e) => {
+                print_error(&format!("Failed to read input: {}", e), flags.json);
+                return 1;
+            }
+        };
+
+        let ok = match parse_line(&line, flags) {
+            Ok(None) => continue,
+            Ok(Some(cmd)) => send_and_print(&mut client, &cmd, flags),
+            Err(LineError::Parse(e)) => {
+                print_parse_error(&e, flags.json);
+                false
+            }
+            Err(LineError::Invalid(msg)) => {
+                print_error(&msg, flags.json);
+                false
+            }
+        };
+
+        if !ok {
+            failed = true;
+            if bail {
+                break;
+            }
+        }
+    }
+
+    if failed {
+        1
+    } else {
+        0
+    }
+}
+
+/// Send one request, (re)connecting if needed, and print its response.
+/// Returns true if the command succeeded.
+pub fn send_and_print(client: &mut Option<Client>, cmd: &Value, flags: &Flags) -> bool {
+    let conn = match client {
+        Some(c) => c,
+        None => match Client::connect(&flags.session, &ConnectOptions::from_flags(flags)) {
+            Ok(c) => client.insert(c),
+            Err(e) => {
+                print_connection_error(&e, flags.json);
+                return false;
+            }
+        },
+    };
+
+    let result = conn.request(cmd);
+    match result {
+        Ok(resp) => {
+            let action = cmd.get("action").and_then(|v| v.as_str());
+            print_response(&resp, flags.json, action);
+            resp.success
+        }
+        Err(e) => {
+            // Drop the connection so the next line starts from a fresh one
+            *client = None;
+            print_connection_error(&e, flags.json);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags() -> Flags {
+        let (mut f, _) = parse_args(&[]).unwrap();
+        f.session = "batch-test".to_string();
+        f
+    }
+
+    #[test]
+    fn test_split_line_plain() {
+        assert_eq!(
+            split_line("click  @e1").unwrap(),
+            vec!["click".to_string(), "@e1".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_split_line_quotes() {
+        assert_eq!(
+            split_line(r#"fill @e3 "hello world" 'it''s'"#).unwrap(),
+            vec!["fill", "@e3", "hello world", "its"]
+        );
+    }
+
+    #[test]
+    fn test_split_line_escapes() {
+        assert_eq!(
+            split_line(r#"type #q "say \"hi\"" a\ b"#).unwrap(),
+            vec!["type", "#q", r#"say "hi""#, "a b"]
+        );
+    }
+
+    #[test]
+    fn test_split_line_empty_quoted_arg() {
+        assert_eq!(
+            split_line(r#"fill @e1 """#).unwrap(),
+            vec!["fill", "@e1", ""]
+        );
+    }
+
+    #[test]
+    fn test_split_line_unterminated() {
+        assert!(split_line(r#"fill @e1 "oops"#).is_err());
+        assert!(split_line("fill @e1 'oops").is_err());
+    }
+
+    #[test]
+    fn test_parse_line_skips_blank_and_comments() {
+        assert!(matches!(parse_line("   ", &flags()), Ok(None)));
+        assert!(matches!(parse_line("# comment", &flags()), Ok(None)));
+    }
+
+    #[test]
+    fn test_parse_line_cli_style() {
+        let cmd = parse_line(r#"fill @e3 "test@example.com""#, &flags())
+            .ok()
+            .flatten()
+            .unwrap();
+        assert_eq!(cmd["action"], "fill");
+        assert_eq!(cmd["value"], "test@example.com");
+    }
+
+    #[test]
+    fn test_parse_line_per_line_flags() {
+        let cmd = parse_line("screenshot --full", &flags())
+            .ok()
+            .flatten()
+            .unwrap();
+        assert_eq!(cmd["fullPage"], true);
+    }
+
+    #[test]
+    fn test_parse_line_per_line_timeout() {
+        let cmd = parse_line("eval --timeout 60000 slowTask()", &flags())
+            .ok()
+            .flatten()
+            .unwrap();
+        assert_eq!(cmd["timeout"], 60000);
+        assert_eq!(cmd["script"], "slowTask()");
+    }
+
+    #[test]
+    fn test_parse_line_rejects_launch_flags() {
+        for line in ["open example.com --headed", "snapshot --session other"] {
+            match parse_line(line, &flags()) {
+                Err(LineError::Invalid(msg)) => assert!(msg.contains("per line"), "{}", msg),
+                _ => panic!("expected {} to be rejected", line),
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_args_bind_through_registry() {
+        let args: Vec<String> = ["batch", "--bail", "--", "--steps.txt"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let inv = bind_local(&args).unwrap();
+        assert!(inv.has("--bail"));
+        assert_eq!(inv.arg(0), Some("--steps.txt"));
+    }
+
+    #[test]
+    fn test_parse_line_json_adds_id() {
+        let cmd = parse_line(r#"{"action":"url"}"#, &flags())
+            .ok()
+            .flatten()
+            .unwrap();
+        assert_eq!(cmd["action"], "url");
+        assert!(cmd["id"].is_string());
+    }
+
+    #[test]
+    fn test_parse_line_json_keeps_id() {
+        let cmd = parse_line(r#"{"id":"mine","action":"url"}"#, &flags())
+            .ok()
+            .flatten()
+            .unwrap();
+        assert_eq!(cmd["id"], "mine");
+    }
+
+    #[test]
+    fn test_parse_line_json_requires_action() {
+        assert!(matches!(
+            parse_line(r#"{"id":"x"}"#, &flags()),
+            Err(LineError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_line_unknown_command() {
+        assert!(matches!(
+            parse_line("frobnicate", &flags()),
+            Err(LineError::Parse(_))
+        ));
+    }
+}
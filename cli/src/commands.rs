@@ -1,8 +1,10 @@
 use base64::{engine::general_purpose::STANDARD, Engine};
 use serde_json::{json, Value};
 use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::flags::Flags;
+use crate::flags::{Flags, GLOBAL_FLAGS};
+use crate::registry::{self, Command};
 
 /// Error type for command parsing with contextual information
 #[derive(Debug)]
@@ -12,7 +14,7 @@ pub enum ParseError {
     /// Command exists but subcommand is invalid
     UnknownSubcommand {
         subcommand: String,
-        valid_options: &'static [&'static str],
+        valid_options: Vec<&'static str>,
     },
     /// Command/subcommand exists but required arguments are missing
     MissingArguments {
@@ -27,6 +29,16 @@ pub enum ParseError {
 }
 
 impl ParseError {
+    /// Machine-readable error type used in JSON output
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            ParseError::UnknownCommand { .. } => "unknown_command",
+            ParseError::UnknownSubcommand { .. } => "unknown_subcommand",
+            ParseError::MissingArguments { .. } => "missing_arguments",
+            ParseError::InvalidValue { .. } => "invalid_value",
+        }
+    }
+
     pub fn format(&self) -> String {
         match self {
             ParseError::UnknownCommand { command } => {
@@ -55,1355 +67,858 @@ impl ParseError {
     }
 }
 
+/// Generate a request id that is unique across processes and within one process:
+/// the pid plus a per-process counter, so replies on a shared connection can be
+/// matched back to the request that produced them.
 pub fn gen_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
     format!(
-        "r{}",
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_micros()
-            % 1000000
+        "r{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
     )
 }
 
+/// Event kinds `events` can filter on
+pub const EVENT_KINDS: &[&str] = &["console", "error", "request", "dialog"];
+
+/// Builds the daemon request for a matched command line
+pub type Builder = fn(&Invocation, &Flags) -> Result<Value, ParseError>;
+
+/// A command line matched against the registry
+pub struct Invocation<'a> {
+    pub id: String,
+    /// Canonical name of the (sub)command that matched
+    pub name: &'static str,
+    /// What was typed to reach it (`get text`), for error messages
+    pub context: String,
+    pub usage: &'static str,
+    /// Positional arguments after the (sub)command name
+    pub args: Vec<&'a str>,
+    /// Flags given, by canonical name
+    flags: Vec<(&'static str, Option<&'a str>)>,
+}
+
+impl<'a> Invocation<'a> {
+    pub fn arg(&self, index: usize) -> Option<&'a str> {
+        self.args.get(index).copied()
+    }
+
+    /// Arguments from `index` on, joined by spaces
+    pub fn rest(&self, index: usize) -> String {
+        self.args.get(index..).unwrap_or_default().join(" ")
+    }
+
+    pub fn has(&self, flag: &str) -> bool {
+        self.flags.iter().any(|(name, _)| *name == flag)
+    }
+
+    /// The value of the last occurrence of `flag`
+    pub fn value(&self, flag: &str) -> Option<&'a str> {
+        self.flags
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == flag)
+            .and_then(|(_, value)| *value)
+    }
+
+    /// A request for `action`, to which the builder adds its fields
+    pub fn request(&self, action: &str) -> Value {
+        json!({ "id": self.id, "action": action })
+    }
+
+    pub fn invalid(&self, message: String) -> ParseError {
+        ParseError::InvalidValue {
+            message,
+            usage: self.usage,
+        }
+    }
+
+    pub fn missing(&self) -> ParseError {
+        ParseError::MissingArguments {
+            context: self.context.clone(),
+            usage: self.usage,
+        }
+    }
+
+    /// Argument `index` as a number
+    fn number<T: std::str::FromStr>(&self, index: usize, what: &str) -> Result<T, ParseError> {
+        let arg = self.args[index];
+        arg.parse()
+            .map_err(|_| self.invalid(format!("Invalid {}: {}", what, arg)))
+    }
+}
+
+/// Parse a command line (without global flags) into a daemon request.
+///
+/// The command, its subcommand, flags and required arguments are resolved
+/// from the registry; the matched entry's builder produces the request.
 pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError> {
-    if args.is_empty() {
+    let Some(name) = args.first() else {
         return Err(ParseError::MissingArguments {
             context: "".to_string(),
             usage: "<command> [args...]",
         });
+    };
+    let command = registry::find(name).ok_or_else(|| ParseError::UnknownCommand {
+        command: name.to_string(),
+    })?;
+    if command.local {
+        return Err(ParseError::InvalidValue {
+            message: format!("{} can only be run as its own agent-browser command", name),
+            usage: command.usage,
+        });
     }
 
-    let cmd = args[0].as_str();
-    let rest: Vec<&str> = args[1..].iter().map(|s| s.as_str()).collect();
-    let id = gen_id();
-
-    match cmd {
-        // === Navigation ===
-        "open" | "goto" | "navigate" => {
-            let url = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: cmd.to_string(),
-                usage: "open <url>",
-            })?;
-            let url_lower = url.to_lowercase();
-            let url = if url_lower.starts_with("http://")
-                || url_lower.starts_with("https://")
-                || url_lower.starts_with("about:")
-                || url_lower.starts_with("data:")
-                || url_lower.starts_with("file:")
-            {
-                url.to_string()
-            } else {
-                format!("https://{}", url)
-            };
-            let mut nav_cmd = json!({ "id": id, "action": "navigate", "url": url });
-            // If --headers flag is set, include headers (scoped to this origin)
-            if let Some(ref headers_json) = flags.headers {
-                if let Ok(headers) = serde_json::from_str::<serde_json::Value>(headers_json) {
-                    nav_cmd["headers"] = headers;
-                }
-            }
-            // Include iOS device info if specified (needed for auto-launch with existing daemon)
-            if flags.provider.as_deref() == Some("ios") {
-                if let Some(ref device) = flags.device {
-                    nav_cmd["iosDevice"] = json!(device);
-                }
-            }
-            Ok(nav_cmd)
-        }
-        "back" => Ok(json!({ "id": id, "action": "back" })),
-        "forward" => Ok(json!({ "id": id, "action": "forward" })),
-        "reload" => Ok(json!({ "id": id, "action": "reload" })),
-
-        // === Core Actions ===
-        "click" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "click".to_string(),
-                usage: "click <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "click", "selector": sel }))
-        }
-        "dblclick" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "dblclick".to_string(),
-                usage: "dblclick <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "dblclick", "selector": sel }))
-        }
-        "fill" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "fill".to_string(),
-                usage: "fill <selector> <text>",
-            })?;
-            Ok(json!({ "id": id, "action": "fill", "selector": sel, "value": rest[1..].join(" ") }))
-        }
-        "type" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "type".to_string(),
-                usage: "type <selector> <text>",
-            })?;
-            Ok(json!({ "id": id, "action": "type", "selector": sel, "text": rest[1..].join(" ") }))
-        }
-        "hover" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "hover".to_string(),
-                usage: "hover <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "hover", "selector": sel }))
-        }
-        "focus" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "focus".to_string(),
-                usage: "focus <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "focus", "selector": sel }))
-        }
-        "check" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "check".to_string(),
-                usage: "check <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "check", "selector": sel }))
-        }
-        "uncheck" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "uncheck".to_string(),
-                usage: "uncheck <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "uncheck", "selector": sel }))
-        }
-        "select" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "select".to_string(),
-                usage: "select <selector> <value...>",
-            })?;
-            let _val = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "select".to_string(),
-                usage: "select <selector> <value...>",
-            })?;
-            let values = &rest[1..];
-            if values.len() == 1 {
-                Ok(json!({ "id": id, "action": "select", "selector": sel, "values": values[0] }))
-            } else {
-                Ok(json!({ "id": id, "action": "select", "selector": sel, "values": values }))
-            }
-        }
-        "drag" => {
-            let src = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "drag".to_string(),
-                usage: "drag <source> <target>",
-            })?;
-            let tgt = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "drag".to_string(),
-                usage: "drag <source> <target>",
-            })?;
-            Ok(json!({ "id": id, "action": "drag", "source": src, "target": tgt }))
-        }
-        "upload" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "upload".to_string(),
-                usage: "upload <selector> <files...>",
-            })?;
-            Ok(json!({ "id": id, "action": "upload", "selector": sel, "files": &rest[1..] }))
-        }
-        "download" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "download".to_string(),
-                usage: "download <selector> <path>",
-            })?;
-            let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "download".to_string(),
-                usage: "download <selector> <path>",
-            })?;
-            Ok(json!({ "id": id, "action": "download", "selector": sel, "path": path }))
-        }
+    let (positionals, given) = bind_flags(command, &args[1..])?;
+    let (spec, context, args) = select(command, positionals)?;
 
-        // === Keyboard ===
-        "press" | "key" => {
-            let key = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "press".to_string(),
-                usage: "press <key>",
-            })?;
-            Ok(json!({ "id": id, "action": "press", "key": key }))
-        }
-        "keydown" => {
-            let key = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "keydown".to_string(),
-                usage: "keydown <key>",
-            })?;
-            Ok(json!({ "id": id, "action": "keydown", "key": key }))
-        }
-        "keyup" => {
-            let key = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "keyup".to_string(),
-                usage: "keyup <key>",
-            })?;
-            Ok(json!({ "id": id, "action": "keyup", "key": key }))
-        }
+    let required = spec.args.iter().filter(|a| a.required).count();
+    if args.len() < required {
+        return Err(ParseError::MissingArguments {
+            context,
+            usage: spec.usage,
+        });
+    }
 
-        // === Scroll ===
-        "scroll" => {
-            let dir = rest.get(0).unwrap_or(&"down");
-            let amount = rest
-                .get(1)
-                .and_then(|s| s.parse::<i32>().ok())
-                .unwrap_or(300);
-            Ok(json!({ "id": id, "action": "scroll", "direction": dir, "amount": amount }))
-        }
-        "scrollintoview" | "scrollinto" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "scrollintoview".to_string(),
-                usage: "scrollintoview <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "scrollintoview", "selector": sel }))
-        }
+    let build = spec.build.expect("every daemon command has a builder");
+    build(
+        &Invocation {
+            id: gen_id(),
+            name: spec.name,
+            context,
+            usage: spec.usage,
+            args,
+            flags: given,
+        },
+        flags,
+    )
+}
 
-        // === Wait ===
-        "wait" => {
-            // Check for --url flag: wait --url "**/dashboard"
-            if let Some(idx) = rest.iter().position(|&s| s == "--url" || s == "-u") {
-                let url = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --url".to_string(),
-                        usage: "wait --url <pattern>",
-                    })?;
-                return Ok(json!({ "id": id, "action": "waitforurl", "url": url }));
-            }
+/// Bind a command that main runs itself (`batch`) against its registry entry:
+/// its flags and arguments, without building a request.
+pub fn bind_local(args: &[String]) -> Result<Invocation<'_>, ParseError> {
+    let Some(name) = args.first() else {
+        return Err(ParseError::MissingArguments {
+            context: "".to_string(),
+            usage: "<command> [args...]",
+        });
+    };
+    let command = registry::find(name).ok_or_else(|| ParseError::UnknownCommand {
+        command: name.to_string(),
+    })?;
+    let (positionals, given) = bind_flags(command, &args[1..])?;
+    let (spec, context, args) = select(command, positionals)?;
+    if args.len() < spec.args.iter().filter(|a| a.required).count() {
+        return Err(ParseError::MissingArguments {
+            context,
+            usage: spec.usage,
+        });
+    }
+    Ok(Invocation {
+        id: gen_id(),
+        name: spec.name,
+        context,
+        usage: spec.usage,
+        args,
+        flags: given,
+    })
+}
 
-            // Check for --load flag: wait --load networkidle
-            if let Some(idx) = rest.iter().position(|&s| s == "--load" || s == "-l") {
-                let state = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --load".to_string(),
-                        usage: "wait --load <state>",
-                    })?;
-                return Ok(json!({ "id": id, "action": "waitforloadstate", "state": state }));
-            }
+type Bound<'a> = (Vec<&'a str>, Vec<(&'static str, Option<&'a str>)>);
+
+/// Separate the command's flags (and their values) from its positionals.
+///
+/// The global flags the command lists are bound too: on the command line they
+/// were already taken out, but batch and shell lines still carry them.
+fn bind_flags<'a>(
+    command: &'static Command,
+    tokens: &'a [String],
+) -> Result<Bound<'a>, ParseError> {
+    let mut specs = registry::flags_of(command);
+    specs.extend(
+        GLOBAL_FLAGS
+            .iter()
+            .filter(|spec| command.globals.contains(&spec.names[0])),
+    );
+    let mut positionals = Vec::new();
+    let mut given = Vec::new();
+    let mut tokens = tokens.iter().map(|s| s.as_str());
+    while let Some(token) = tokens.next() {
+        // Everything after `--` is an argument
+        if token == "--" {
+            positionals.extend(tokens.by_ref());
+            break;
+        }
+        let Some(spec) = specs.iter().find(|spec| spec.names.contains(&token)) else {
+            positionals.push(token);
+            continue;
+        };
+        let value = match spec.value {
+            Some(_) => Some(tokens.next().ok_or_else(|| ParseError::MissingArguments {
+                context: format!("{} {}", command.name, token),
+                usage: command.usage,
+            })?),
+            None => None,
+        };
+        given.push((spec.names[0], value));
+    }
+    Ok((positionals, given))
+}
 
-            // Check for --fn flag: wait --fn "window.ready === true"
-            if let Some(idx) = rest.iter().position(|&s| s == "--fn" || s == "-f") {
-                let expr = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --fn".to_string(),
-                        usage: "wait --fn <expression>",
-                    })?;
-                return Ok(json!({ "id": id, "action": "waitforfunction", "expression": expr }));
-            }
+/// The (sub)command the positionals select, the words that selected it and
+/// the arguments left for it
+fn select<'a>(
+    command: &'static Command,
+    mut positionals: Vec<&'a str>,
+) -> Result<(&'static Command, String, Vec<&'a str>), ParseError> {
+    if command.subcommands.is_empty() {
+        return Ok((command, command.name.to_string(), positionals));
+    }
+    if let Some(sub) = positionals
+        .first()
+        .and_then(|word| registry::find_subcommand(command, word))
+    {
+        positionals.remove(0);
+        return Ok((sub, format!("{} {}", command.name, sub.name), positionals));
+    }
+    // A command with a builder of its own handles other arguments (`tab 2`)
+    if command.build.is_some() && !positionals.is_empty() {
+        return Ok((command, command.name.to_string(), positionals));
+    }
+    match (positionals.first(), command.default_subcommand) {
+        (None, Some(default)) => {
+            let sub = registry::find_subcommand(command, default).expect("default subcommand");
+            Ok((sub, format!("{} {}", command.name, sub.name), positionals))
+        }
+        (None, None) => Err(ParseError::MissingArguments {
+            context: command.name.to_string(),
+            usage: command.usage,
+        }),
+        (Some(word), _) => Err(ParseError::UnknownSubcommand {
+            subcommand: word.to_string(),
+            valid_options: registry::subcommand_names(command),
+        }),
+    }
+}
 
-            // Check for --text flag: wait --text "Welcome"
-            if let Some(idx) = rest.iter().position(|&s| s == "--text" || s == "-t") {
-                let text = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --text".to_string(),
-                        usage: "wait --text <text>",
-                    })?;
-                // Use getByText locator to wait for text to appear
-                return Ok(
-                    json!({ "id": id, "action": "wait", "selector": format!("text={}", text) }),
-                );
-            }
+// === Builders, referenced from the registry ===
 
-            // Check for --download flag: wait --download [path] [--timeout ms]
-            if rest.iter().any(|&s| s == "--download" || s == "-d") {
-                let mut cmd = json!({ "id": id, "action": "waitfordownload" });
-                // Check for optional path (first non-flag argument after --download)
-                let download_idx = rest
-                    .iter()
-                    .position(|&s| s == "--download" || s == "-d")
-                    .unwrap();
-                if let Some(path) = rest.get(download_idx + 1) {
-                    if !path.starts_with("--") {
-                        cmd["path"] = json!(path);
-                    }
-                }
-                // Check for optional timeout
-                if let Some(idx) = rest.iter().position(|&s| s == "--timeout") {
-                    if let Some(timeout_str) = rest.get(idx + 1) {
-                        if let Ok(timeout) = timeout_str.parse::<u64>() {
-                            cmd["timeout"] = json!(timeout);
-                        }
-                    }
-                }
-                return Ok(cmd);
-            }
+/// `back`, `forward`, `reload` and `close`
+pub fn parse_simple(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    Ok(inv.request(inv.name))
+}
 
-            // Default: selector or timeout
-            if let Some(arg) = rest.get(0) {
-                if arg.parse::<u64>().is_ok() {
-                    Ok(
-                        json!({ "id": id, "action": "wait", "timeout": arg.parse::<u64>().unwrap() }),
-                    )
-                } else {
-                    Ok(json!({ "id": id, "action": "wait", "selector": arg }))
-                }
-            } else {
-                Err(ParseError::MissingArguments {
-                    context: "wait".to_string(),
-                    usage: "wait <selector|ms|--url|--load|--fn|--text>",
-                })
-            }
-        }
+/// Commands whose action is their name and whose one argument is a selector
+pub fn parse_selector_action(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let mut cmd = inv.request(inv.name);
+    cmd["selector"] = json!(inv.args[0]);
+    Ok(cmd)
+}
 
-        // === Screenshot/PDF ===
-        "screenshot" => {
-            // screenshot [selector] [path]
-            // selector: @ref or CSS selector
-            // path: file path (contains / or . or ends with known extension)
-            let (selector, path) = match (rest.get(0), rest.get(1)) {
-                (Some(first), Some(second)) => {
-                    // Two args: first is selector, second is path
-                    (Some(*first), Some(*second))
-                }
-                (Some(first), None) => {
-                    // One arg: determine if it's a selector or a path
-                    let is_relative_path = first.starts_with("./") || first.starts_with("../");
-                    let is_selector = !is_relative_path
-                        && (first.starts_with('.')
-                            || first.starts_with('#')
-                            || first.starts_with('@'));
-                    let has_path_extension = first.ends_with(".png")
-                        || first.ends_with(".jpg")
-                        || first.ends_with(".jpeg")
-                        || first.ends_with(".webp");
-                    let is_path = is_relative_path || first.contains('/') || has_path_extension;
-                    if is_selector || !is_path {
-                        (Some(*first), None)
-                    } else {
-                        (None, Some(*first))
-                    }
-                }
-                _ => (None, None),
-            };
-            Ok(
-                json!({ "id": id, "action": "screenshot", "path": path, "selector": selector, "fullPage": flags.full }),
-            )
-        }
-        "pdf" => {
-            let path = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "pdf".to_string(),
-                usage: "pdf <path>",
-            })?;
-            Ok(json!({ "id": id, "action": "pdf", "path": path }))
-        }
+/// `press`, `keydown` and `keyup`
+pub fn parse_key_action(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let mut cmd = inv.request(inv.name);
+    cmd["key"] = json!(inv.args[0]);
+    Ok(cmd)
+}
 
-        // === Snapshot ===
-        "snapshot" => {
-            let mut cmd = json!({ "id": id, "action": "snapshot" });
-            let obj = cmd.as_object_mut().unwrap();
-            let mut i = 0;
-            while i < rest.len() {
-                match rest[i] {
-                    "-i" | "--interactive" => {
-                        obj.insert("interactive".to_string(), json!(true));
-                    }
-                    "-c" | "--compact" => {
-                        obj.insert("compact".to_string(), json!(true));
-                    }
-                    "-C" | "--cursor" => {
-                        obj.insert("cursor".to_string(), json!(true));
-                    }
-                    "-d" | "--depth" => {
-                        if let Some(d) = rest.get(i + 1) {
-                            if let Ok(n) = d.parse::<i32>() {
-                                obj.insert("maxDepth".to_string(), json!(n));
-                                i += 1;
-                            }
-                        }
-                    }
-                    "-s" | "--selector" => {
-                        if let Some(s) = rest.get(i + 1) {
-                            obj.insert("selector".to_string(), json!(s));
-                            i += 1;
-                        }
-                    }
-                    _ => {}
-                }
-                i += 1;
-            }
-            Ok(cmd)
-        }
+pub fn parse_open(inv: &Invocation, flags: &Flags) -> Result<Value, ParseError> {
+    let url = inv.args[0];
+    let url_lower = url.to_lowercase();
+    let url = if url_lower.starts_with("http://")
+        || url_lower.starts_with("https://")
+        || url_lower.starts_with("about:")
+        || url_lower.starts_with("data:")
+        || url_lower.starts_with("file:")
+    {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    };
+    let mut nav_cmd = inv.request("navigate");
+    nav_cmd["url"] = json!(url);
+    // If --headers flag is set, include headers (scoped to this origin)
+    if let Some(ref headers_json) = flags.headers {
+        if let Ok(headers) = serde_json::from_str::<serde_json::Value>(headers_json) {
+            nav_cmd["headers"] = headers;
+        }
+    }
+    // Include iOS device info if specified (needed for auto-launch with existing daemon)
+    if flags.provider.as_deref() == Some("ios") {
+        if let Some(ref device) = flags.device {
+            nav_cmd["iosDevice"] = json!(device);
+        }
+    }
+    Ok(nav_cmd)
+}
 
-        // === Eval ===
-        "eval" => {
-            // Check for flags: -b/--base64 or --stdin
-            let (is_base64, is_stdin, script_parts): (bool, bool, &[&str]) =
-                if rest.first() == Some(&"-b") || rest.first() == Some(&"--base64") {
-                    (true, false, &rest[1..])
-                } else if rest.first() == Some(&"--stdin") {
-                    (false, true, &rest[1..])
-                } else {
-                    (false, false, rest.as_slice())
-                };
-
-            let script = if is_stdin {
-                // Read script from stdin
-                let stdin = io::stdin();
-                let lines: Vec<String> = stdin.lock().lines()
-                    .map(|l| l.unwrap_or_default())
-                    .collect();
-                lines.join("\n")
-            } else {
-                let raw_script = script_parts.join(" ");
-                if is_base64 {
-                    let decoded = STANDARD.decode(&raw_script).map_err(|_| ParseError::InvalidValue {
-                        message: "Invalid base64 encoding".to_string(),
-                        usage: "eval -b <base64-encoded-script>",
-                    })?;
-                    String::from_utf8(decoded).map_err(|_| ParseError::InvalidValue {
-                        message: "Base64 decoded to invalid UTF-8".to_string(),
-                        usage: "eval -b <base64-encoded-script>",
-                    })?
-                } else {
-                    raw_script
-                }
-            };
-            Ok(json!({ "id": id, "action": "evaluate", "script": script }))
-        }
+pub fn parse_fill(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    Ok(json!({ "id": inv.id, "action": "fill", "selector": inv.args[0], "value": inv.rest(1) }))
+}
 
-        // === Close ===
-        "close" | "quit" | "exit" => Ok(json!({ "id": id, "action": "close" })),
-
-        // === Connect (CDP) ===
-        "connect" => {
-            let endpoint = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "connect".to_string(),
-                usage: "connect <port|url>",
-            })?;
-            // Check if it's a URL (ws://, wss://, http://, https://)
-            if endpoint.starts_with("ws://")
-                || endpoint.starts_with("wss://")
-                || endpoint.starts_with("http://")
-                || endpoint.starts_with("https://")
-            {
-                Ok(json!({ "id": id, "action": "launch", "cdpUrl": endpoint }))
-            } else {
-                // It's a port number - validate and use cdpPort field
-                let port: u16 = match endpoint.parse::<u32>() {
-                    Ok(p) if p == 0 => {
-                        return Err(ParseError::InvalidValue {
-                            message: "Invalid port: port must be greater than 0".to_string(),
-                            usage: "connect <port|url>",
-                        });
-                    }
-                    Ok(p) if p > 65535 => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!(
-                                "Invalid port: {} is out of range (valid range: 1-65535)",
-                                p
-                            ),
-                            usage: "connect <port|url>",
-                        });
-                    }
-                    Ok(p) => p as u16,
-                    Err(_) => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!(
-                                "Invalid value: '{}' is not a valid port number or URL",
-                                endpoint
-                            ),
-                            usage: "connect <port|url>",
-                        });
-                    }
-                };
-                Ok(json!({ "id": id, "action": "launch", "cdpPort": port }))
-            }
-        }
+pub fn parse_type(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    Ok(json!({ "id": inv.id, "action": "type", "selector": inv.args[0], "text": inv.rest(1) }))
+}
 
-        // === Get ===
-        "get" => parse_get(&rest, &id),
-
-        // === Is (state checks) ===
-        "is" => parse_is(&rest, &id),
-
-        // === Find (locators) ===
-        "find" => parse_find(&rest, &id),
-
-        // === Mouse ===
-        "mouse" => parse_mouse(&rest, &id),
-
-        // === Set (browser settings) ===
-        "set" => parse_set(&rest, &id),
-
-        // === Network ===
-        "network" => parse_network(&rest, &id),
-
-        // === Storage ===
-        "storage" => parse_storage(&rest, &id),
-
-        // === Cookies ===
-        "cookies" => {
-            let op = rest.get(0).unwrap_or(&"get");
-            match *op {
-                "set" => {
-                    let name = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "cookies set".to_string(),
-                        usage: "cookies set <name> <value> [--url <url>] [--domain <domain>] [--path <path>] [--httpOnly] [--secure] [--sameSite <Strict|Lax|None>] [--expires <timestamp>]",
-                    })?;
-                    let value = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                        context: "cookies set".to_string(),
-                        usage: "cookies set <name> <value> [--url <url>] [--domain <domain>] [--path <path>] [--httpOnly] [--secure] [--sameSite <Strict|Lax|None>] [--expires <timestamp>]",
-                    })?;
-
-                    let mut cookie = json!({ "name": name, "value": value });
-
-                    // Parse optional flags
-                    let mut i = 3;
-                    while i < rest.len() {
-                        match rest[i] {
-                            "--url" => {
-                                if let Some(url) = rest.get(i + 1) {
-                                    cookie["url"] = json!(url);
-                                    i += 2;
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --url".to_string(),
-                                        usage: "--url <url>",
-                                    });
-                                }
-                            }
-                            "--domain" => {
-                                if let Some(domain) = rest.get(i + 1) {
-                                    cookie["domain"] = json!(domain);
-                                    i += 2;
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --domain".to_string(),
-                                        usage: "--domain <domain>",
-                                    });
-                                }
-                            }
-                            "--path" => {
-                                if let Some(path) = rest.get(i + 1) {
-                                    cookie["path"] = json!(path);
-                                    i += 2;
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --path".to_string(),
-                                        usage: "--path <path>",
-                                    });
-                                }
-                            }
-                            "--httpOnly" => {
-                                cookie["httpOnly"] = json!(true);
-                                i += 1;
-                            }
-                            "--secure" => {
-                                cookie["secure"] = json!(true);
-                                i += 1;
-                            }
-                            "--sameSite" => {
-                                if let Some(same_site) = rest.get(i + 1) {
-                                    // Validate sameSite value
-                                    if *same_site == "Strict"
-                                        || *same_site == "Lax"
-                                        || *same_site == "None"
-                                    {
-                                        cookie["sameSite"] = json!(same_site);
-                                        i += 2;
-                                    } else {
-                                        return Err(ParseError::MissingArguments {
-                                            context: "cookies set --sameSite".to_string(),
-                                            usage: "--sameSite <Strict|Lax|None>",
-                                        });
-                                    }
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --sameSite".to_string(),
-                                        usage: "--sameSite <Strict|Lax|None>",
-                                    });
-                                }
-                            }
-                            "--expires" => {
-                                if let Some(expires_str) = rest.get(i + 1) {
-                                    if let Ok(expires) = expires_str.parse::<i64>() {
-                                        cookie["expires"] = json!(expires);
-                                        i += 2;
-                                    } else {
-                                        return Err(ParseError::MissingArguments {
-                                            context: "cookies set --expires".to_string(),
-                                            usage: "--expires <timestamp>",
-                                        });
-                                    }
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --expires".to_string(),
-                                        usage: "--expires <timestamp>",
-                                    });
-                                }
-                            }
-                            _ => {
-                                // Unknown flag, skip it (or could error)
-                                i += 1;
-                            }
-                        }
-                    }
-
-                    Ok(json!({ "id": id, "action": "cookies_set", "cookies": [cookie] }))
-                }
-                "clear" => Ok(json!({ "id": id, "action": "cookies_clear" })),
-                _ => Ok(json!({ "id": id, "action": "cookies_get" })),
-            }
-        }
+pub fn parse_select(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let values = &inv.args[1..];
+    if values.len() == 1 {
+        Ok(
+            json!({ "id": inv.id, "action": "select", "selector": inv.args[0], "values": values[0] }),
+        )
+    } else {
+        Ok(json!({ "id": inv.id, "action": "select", "selector": inv.args[0], "values": values }))
+    }
+}
 
-        // === Tabs ===
-        "tab" => match rest.get(0).map(|s| *s) {
-            Some("new") => {
-                let mut cmd = json!({ "id": id, "action": "tab_new" });
-                if let Some(url) = rest.get(1) {
-                    cmd["url"] = json!(url);
-                }
-                Ok(cmd)
-            }
-            Some("list") => Ok(json!({ "id": id, "action": "tab_list" })),
-            Some("close") => {
-                let mut cmd = json!({ "id": id, "action": "tab_close" });
-                if let Some(index) = rest.get(1).and_then(|s| s.parse::<i32>().ok()) {
-                    cmd["index"] = json!(index);
-                }
-                Ok(cmd)
-            }
-            Some(n) if n.parse::<i32>().is_ok() => {
-                Ok(json!({ "id": id, "action": "tab_switch", "index": n.parse::<i32>().unwrap() }))
-            }
-            _ => Ok(json!({ "id": id, "action": "tab_list" })),
-        },
+pub fn parse_drag(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    Ok(json!({ "id": inv.id, "action": "drag", "source": inv.args[0], "target": inv.args[1] }))
+}
 
-        // === Window ===
-        "window" => {
-            const VALID: &[&str] = &["new"];
-            match rest.get(0).map(|s| *s) {
-                Some("new") => Ok(json!({ "id": id, "action": "window_new" })),
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "window".to_string(),
-                    usage: "window <new>",
-                }),
-            }
-        }
+pub fn parse_upload(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    Ok(
+        json!({ "id": inv.id, "action": "upload", "selector": inv.args[0], "files": &inv.args[1..] }),
+    )
+}
+
+pub fn parse_download(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    Ok(json!({ "id": inv.id, "action": "download", "selector": inv.args[0], "path": inv.args[1] }))
+}
 
-        // === Frame ===
-        "frame" => {
-            if rest.get(0).map(|s| *s) == Some("main") {
-                Ok(json!({ "id": id, "action": "mainframe" }))
+pub fn parse_scroll(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let dir = inv.arg(0).unwrap_or("down");
+    let amount = inv
+        .arg(1)
+        .and_then(|s| s.parse::<i32>().ok())
+        .unwrap_or(300);
+    Ok(json!({ "id": inv.id, "action": "scroll", "direction": dir, "amount": amount }))
+}
+
+pub fn parse_wait(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let mut cmd = if let Some(url) = inv.value("--url") {
+        json!({ "id": inv.id, "action": "waitforurl", "url": url })
+    } else if let Some(state) = inv.value("--load") {
+        json!({ "id": inv.id, "action": "waitforloadstate", "state": state })
+    } else if let Some(expr) = inv.value("--fn") {
+        json!({ "id": inv.id, "action": "waitforfunction", "expression": expr })
+    } else if let Some(text) = inv.value("--text") {
+        // Use getByText locator to wait for text to appear
+        json!({ "id": inv.id, "action": "wait", "selector": format!("text={}", text) })
+    } else if inv.has("--download") {
+        let mut cmd = inv.request("waitfordownload");
+        if let Some(path) = inv.arg(0) {
+            cmd["path"] = json!(path);
+        }
+        cmd
+    } else {
+        match inv.arg(0) {
+            Some(arg) => match arg.parse::<u64>() {
+                Ok(ms) => json!({ "id": inv.id, "action": "wait", "timeout": ms }),
+                Err(_) => json!({ "id": inv.id, "action": "wait", "selector": arg }),
+            },
+            None => return Err(inv.missing()),
+        }
+    };
+    // On the command line the global --timeout is added when the request is
+    // sent; batch and shell lines bring their own
+    if let Some(ms) = inv.value("--timeout") {
+        let ms: u64 = ms
+            .parse()
+            .map_err(|_| inv.invalid(format!("Invalid timeout: {}", ms)))?;
+        // `wait <ms>` already says how long
+        cmd.as_object_mut()
+            .unwrap()
+            .entry("timeout")
+            .or_insert(json!(ms));
+    }
+    Ok(cmd)
+}
+
+pub fn parse_screenshot(inv: &Invocation, flags: &Flags) -> Result<Value, ParseError> {
+    // screenshot [selector] [path]
+    // selector: @ref or CSS selector
+    // path: file path (contains / or . or ends with known extension)
+    let (selector, path) = match (inv.arg(0), inv.arg(1)) {
+        (Some(first), Some(second)) => {
+            // Two args: first is selector, second is path
+            (Some(first), Some(second))
+        }
+        (Some(first), None) => {
+            // One arg: determine if it's a selector or a path
+            let is_relative_path = first.starts_with("./") || first.starts_with("../");
+            let is_selector = !is_relative_path
+                && (first.starts_with('.') || first.starts_with('#') || first.starts_with('@'));
+            let has_path_extension = first.ends_with(".png")
+                || first.ends_with(".jpg")
+                || first.ends_with(".jpeg")
+                || first.ends_with(".webp");
+            let is_path = is_relative_path || first.contains('/') || has_path_extension;
+            if is_selector || !is_path {
+                (Some(first), None)
             } else {
-                let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                    context: "frame".to_string(),
-                    usage: "frame <selector|main>",
-                })?;
-                Ok(json!({ "id": id, "action": "frame", "selector": sel }))
+                (None, Some(first))
             }
         }
+        _ => (None, None),
+    };
+    Ok(
+        json!({ "id": inv.id, "action": "screenshot", "path": path, "selector": selector, "fullPage": flags.full }),
+    )
+}
 
-        // === Dialog ===
-        "dialog" => {
-            const VALID: &[&str] = &["accept", "dismiss"];
-            match rest.get(0).map(|s| *s) {
-                Some("accept") => {
-                    let mut cmd = json!({ "id": id, "action": "dialog", "response": "accept" });
-                    if let Some(prompt_text) = rest.get(1) {
-                        cmd["promptText"] = json!(prompt_text);
-                    }
-                    Ok(cmd)
-                }
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "dialog".to_string(),
-                    usage: "dialog <accept|dismiss> [text]",
-                }),
-            }
-        }
+pub fn parse_pdf(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    Ok(json!({ "id": inv.id, "action": "pdf", "path": inv.args[0] }))
+}
 
-        // === Debug ===
-        "trace" => {
-            const VALID: &[&str] = &["start", "stop"];
-            match rest.get(0).map(|s| *s) {
-                Some("start") => Ok(json!({ "id": id, "action": "trace_start" })),
-                Some("stop") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "trace stop".to_string(),
-                        usage: "trace stop <path>",
-                    })?;
-                    Ok(json!({ "id": id, "action": "trace_stop", "path": path }))
-                }
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "trace".to_string(),
-                    usage: "trace <start|stop> [path]",
-                }),
-            }
+pub fn parse_snapshot(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let mut cmd = inv.request("snapshot");
+    for (flag, field) in [
+        ("--interactive", "interactive"),
+        ("--compact", "compact"),
+        ("--cursor", "cursor"),
+    ] {
+        if inv.has(flag) {
+            cmd[field] = json!(true);
         }
+    }
+    if let Some(depth) = inv.value("--depth") {
+        let depth: i32 = depth
+            .parse()
+            .map_err(|_| inv.invalid(format!("Invalid depth: {}", depth)))?;
+        cmd["maxDepth"] = json!(depth);
+    }
+    if let Some(selector) = inv.value("--selector") {
+        cmd["selector"] = json!(selector);
+    }
+    Ok(cmd)
+}
 
-        // === Recording (Playwright native video recording) ===
-        "record" => {
-            const VALID: &[&str] = &["start", "stop", "restart"];
-            match rest.get(0).map(|s| *s) {
-                Some("start") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "record start".to_string(),
-                        usage: "record start <output.webm> [url]",
-                    })?;
-                    // Optional URL parameter
-                    let url = rest.get(2);
-                    let mut cmd = json!({ "id": id, "action": "recording_start", "path": path });
-                    if let Some(u) = url {
-                        // Add https:// prefix if needed
-                        let url_str = if u.starts_with("http") {
-                            u.to_string()
-                        } else {
-                            format!("https://{}", u)
-                        };
-                        cmd["url"] = json!(url_str);
-                    }
-                    Ok(cmd)
-                }
-                Some("stop") => Ok(json!({ "id": id, "action": "recording_stop" })),
-                Some("restart") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "record restart".to_string(),
-                        usage: "record restart <output.webm> [url]",
-                    })?;
-                    // Optional URL parameter
-                    let url = rest.get(2);
-                    let mut cmd = json!({ "id": id, "action": "recording_restart", "path": path });
-                    if let Some(u) = url {
-                        // Add https:// prefix if needed
-                        let url_str = if u.starts_with("http") {
-                            u.to_string()
-                        } else {
-                            format!("https://{}", u)
-                        };
-                        cmd["url"] = json!(url_str);
-                    }
-                    Ok(cmd)
-                }
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "record".to_string(),
-                    usage: "record <start|stop|restart> [path] [url]",
-                }),
-            }
-        }
-        "console" => {
-            let clear = rest.iter().any(|&s| s == "--clear");
-            Ok(json!({ "id": id, "action": "console", "clear": clear }))
-        }
-        "errors" => {
-            let clear = rest.iter().any(|&s| s == "--clear");
-            Ok(json!({ "id": id, "action": "errors", "clear": clear }))
-        }
-        "highlight" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "highlight".to_string(),
-                usage: "highlight <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "highlight", "selector": sel }))
-        }
+pub fn parse_eval(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let script = if inv.has("--stdin") {
+        // Read script from stdin
+        let stdin = io::stdin();
+        let lines: Vec<String> = stdin
+            .lock()
+            .lines()
+            .map(|l| l.unwrap_or_default())
+            .collect();
+        lines.join("\n")
+    } else if inv.has("--base64") {
+        let decoded = STANDARD
+            .decode(inv.rest(0))
+            .map_err(|_| inv.invalid("Invalid base64 encoding".to_string()))?;
+        String::from_utf8(decoded)
+            .map_err(|_| inv.invalid("Base64 decoded to invalid UTF-8".to_string()))?
+    } else {
+        inv.rest(0)
+    };
+    Ok(json!({ "id": inv.id, "action": "evaluate", "script": script }))
+}
 
-        // === State ===
-        "state" => {
-            const VALID: &[&str] = &["save", "load"];
-            match rest.get(0).map(|s| *s) {
-                Some("save") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "state save".to_string(),
-                        usage: "state save <path>",
-                    })?;
-                    Ok(json!({ "id": id, "action": "state_save", "path": path }))
-                }
-                Some("load") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "state load".to_string(),
-                        usage: "state load <path>",
-                    })?;
-                    Ok(json!({ "id": id, "action": "state_load", "path": path }))
-                }
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "state".to_string(),
-                    usage: "state <save|load> <path>",
-                }),
-            }
-        }
+pub fn parse_connect(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let endpoint = inv.args[0];
+    // Check if it's a URL (ws://, wss://, http://, https://)
+    if endpoint.starts_with("ws://")
+        || endpoint.starts_with("wss://")
+        || endpoint.starts_with("http://")
+        || endpoint.starts_with("https://")
+    {
+        return Ok(json!({ "id": inv.id, "action": "launch", "cdpUrl": endpoint }));
+    }
+    // It's a port number - validate and use cdpPort field
+    let port: u16 = match endpoint.parse::<u32>() {
+        Ok(0) => {
+            return Err(inv.invalid("Invalid port: port must be greater than 0".to_string()));
+        }
+        Ok(p) if p > 65535 => {
+            return Err(inv.invalid(format!(
+                "Invalid port: {} is out of range (valid range: 1-65535)",
+                p
+            )));
+        }
+        Ok(p) => p as u16,
+        Err(_) => {
+            return Err(inv.invalid(format!(
+                "Invalid value: '{}' is not a valid port number or URL",
+                endpoint
+            )));
+        }
+    };
+    Ok(json!({ "id": inv.id, "action": "launch", "cdpPort": port }))
+}
 
-        // === iOS-specific commands ===
-        "tap" => {
-            // Alias for click (semantic clarity for touch interfaces)
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "tap".to_string(),
-                usage: "tap <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "tap", "selector": sel }))
-        }
-        "swipe" => {
-            let direction = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "swipe".to_string(),
-                usage: "swipe <up|down|left|right> [distance]",
-            })?;
-            let valid_directions = ["up", "down", "left", "right"];
-            if !valid_directions.contains(direction) {
-                return Err(ParseError::InvalidValue {
-                    message: format!("Invalid swipe direction: {}", direction),
-                    usage: "swipe <up|down|left|right> [distance]",
-                });
-            }
-            let mut cmd = json!({ "id": id, "action": "swipe", "direction": direction });
-            if let Some(distance) = rest.get(1) {
-                if let Ok(d) = distance.parse::<u32>() {
-                    cmd.as_object_mut().unwrap().insert("distance".to_string(), json!(d));
-                }
-            }
-            Ok(cmd)
-        }
-        "device" => {
-            match rest.get(0).map(|s| *s) {
-                Some("list") | None => {
-                    // List available iOS simulators
-                    Ok(json!({ "id": id, "action": "device_list" }))
-                }
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: &["list"],
-                }),
-            }
-        }
+pub fn parse_get(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let action = match inv.name {
+        "text" => "gettext",
+        "html" => "innerhtml",
+        "value" => "inputvalue",
+        "attr" => "getattribute",
+        "box" => "boundingbox",
+        // url, title, count, styles
+        other => other,
+    };
+    let mut cmd = inv.request(action);
+    if !matches!(inv.name, "url" | "title") {
+        cmd["selector"] = json!(inv.args[0]);
+    }
+    if inv.name == "attr" {
+        cmd["attribute"] = json!(inv.args[1]);
+    }
+    Ok(cmd)
+}
 
-        _ => Err(ParseError::UnknownCommand {
-            command: cmd.to_string(),
-        }),
-    }
+pub fn parse_is(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let mut cmd = inv.request(&format!("is{}", inv.name));
+    cmd["selector"] = json!(inv.args[0]);
+    Ok(cmd)
 }
 
-fn parse_get(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &[
-        "text", "html", "value", "attr", "url", "title", "count", "box", "styles",
-    ];
-
-    match rest.get(0).map(|s| *s) {
-        Some("text") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get text".to_string(),
-                usage: "get text <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "gettext", "selector": sel }))
+pub fn parse_find(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let name = inv.value("--name");
+    let exact = inv.has("--exact");
+
+    // `nth` takes an index before the selector
+    let (index, args) = if inv.name == "nth" {
+        (Some(inv.number::<i32>(0, "index")?), &inv.args[1..])
+    } else {
+        (None, &inv.args[..])
+    };
+    let Some(&value) = args.first() else {
+        return Err(inv.missing());
+    };
+    let subaction = args.get(1).copied().unwrap_or("click");
+    let fill_value = args.get(2..).filter(|rest| !rest.is_empty());
+
+    let mut cmd = match inv.name {
+        "role" => {
+            json!({ "id": inv.id, "action": "getbyrole", "role": value, "subaction": subaction, "name": name, "exact": exact })
         }
-        Some("html") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get html".to_string(),
-                usage: "get html <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "innerhtml", "selector": sel }))
+        "text" => {
+            json!({ "id": inv.id, "action": "getbytext", "text": value, "subaction": subaction, "exact": exact })
         }
-        Some("value") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get value".to_string(),
-                usage: "get value <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "inputvalue", "selector": sel }))
+        "label" => {
+            json!({ "id": inv.id, "action": "getbylabel", "label": value, "subaction": subaction, "exact": exact })
         }
-        Some("attr") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get attr".to_string(),
-                usage: "get attr <selector> <attribute>",
-            })?;
-            let attr = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "get attr".to_string(),
-                usage: "get attr <selector> <attribute>",
-            })?;
-            Ok(json!({ "id": id, "action": "getattribute", "selector": sel, "attribute": attr }))
+        "placeholder" => {
+            json!({ "id": inv.id, "action": "getbyplaceholder", "placeholder": value, "subaction": subaction, "exact": exact })
         }
-        Some("url") => Ok(json!({ "id": id, "action": "url" })),
-        Some("title") => Ok(json!({ "id": id, "action": "title" })),
-        Some("count") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get count".to_string(),
-                usage: "get count <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "count", "selector": sel }))
+        "alt" => {
+            json!({ "id": inv.id, "action": "getbyalttext", "text": value, "subaction": subaction, "exact": exact })
         }
-        Some("box") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get box".to_string(),
-                usage: "get box <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "boundingbox", "selector": sel }))
+        "title" => {
+            json!({ "id": inv.id, "action": "getbytitle", "text": value, "subaction": subaction, "exact": exact })
         }
-        Some("styles") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get styles".to_string(),
-                usage: "get styles <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "styles", "selector": sel }))
+        "testid" => {
+            json!({ "id": inv.id, "action": "getbytestid", "testId": value, "subaction": subaction })
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "get".to_string(),
-            usage: "get <text|html|value|attr|url|title|count|box|styles> [args...]",
-        }),
-    }
-}
-
-fn parse_is(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["visible", "enabled", "checked"];
-
-    match rest.get(0).map(|s| *s) {
-        Some("visible") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "is visible".to_string(),
-                usage: "is visible <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "isvisible", "selector": sel }))
-        }
-        Some("enabled") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "is enabled".to_string(),
-                usage: "is enabled <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "isenabled", "selector": sel }))
+        // first, last, nth
+        _ => {
+            let index = index.unwrap_or(if inv.name == "last" { -1 } else { 0 });
+            json!({ "id": inv.id, "action": "nth", "selector": value, "index": index, "subaction": subaction })
         }
-        Some("checked") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "is checked".to_string(),
-                usage: "is checked <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "ischecked", "selector": sel }))
-        }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "is".to_string(),
-            usage: "is <visible|enabled|checked> <selector>",
-        }),
+    };
+    // Text and alt/title locators only click or read, so no value is sent
+    if let (Some(rest), false) = (fill_value, matches!(inv.name, "text" | "alt" | "title")) {
+        cmd["value"] = json!(rest.join(" "));
     }
+    Ok(cmd)
 }
 
-fn parse_find(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &[
-        "role",
-        "text",
-        "label",
-        "placeholder",
-        "alt",
-        "title",
-        "testid",
-        "first",
-        "last",
-        "nth",
-    ];
-
-    let locator = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-        context: "find".to_string(),
-        usage: "find <locator> <value> [action] [text]",
-    })?;
-
-    let name_idx = rest.iter().position(|&s| s == "--name");
-    let name = name_idx.and_then(|i| rest.get(i + 1).map(|s| *s));
-    let exact = rest.iter().any(|&s| s == "--exact");
-
-    match *locator {
-        "role" | "text" | "label" | "placeholder" | "alt" | "title" | "testid" | "first"
-        | "last" => {
-            let value = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: format!("find {}", locator),
-                usage: match *locator {
-                    "role" => "find role <role> [action] [--name <name>] [--exact]",
-                    "text" => "find text <text> [action] [--exact]",
-                    "label" => "find label <label> [action] [text] [--exact]",
-                    "placeholder" => "find placeholder <text> [action] [text] [--exact]",
-                    "alt" => "find alt <text> [action] [--exact]",
-                    "title" => "find title <text> [action] [--exact]",
-                    "testid" => "find testid <id> [action] [text]",
-                    "first" => "find first <selector> [action] [text]",
-                    "last" => "find last <selector> [action] [text]",
-                    _ => "find <locator> <value> [action] [text]",
-                },
-            })?;
-            let subaction = rest.get(2).unwrap_or(&"click");
-            let fill_value = if rest.len() > 3 {
-                Some(rest[3..].join(" "))
-            } else {
-                None
-            };
-
-            match *locator {
-                "role" => {
-                    let mut cmd = json!({ "id": id, "action": "getbyrole", "role": value, "subaction": subaction, "name": name, "exact": exact });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "text" => Ok(
-                    json!({ "id": id, "action": "getbytext", "text": value, "subaction": subaction, "exact": exact }),
-                ),
-                "label" => {
-                    let mut cmd = json!({ "id": id, "action": "getbylabel", "label": value, "subaction": subaction, "exact": exact });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "placeholder" => {
-                    let mut cmd = json!({ "id": id, "action": "getbyplaceholder", "placeholder": value, "subaction": subaction, "exact": exact });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "alt" => Ok(
-                    json!({ "id": id, "action": "getbyalttext", "text": value, "subaction": subaction, "exact": exact }),
-                ),
-                "title" => Ok(
-                    json!({ "id": id, "action": "getbytitle", "text": value, "subaction": subaction, "exact": exact }),
-                ),
-                "testid" => {
-                    let mut cmd = json!({ "id": id, "action": "getbytestid", "testId": value, "subaction": subaction });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "first" => {
-                    let mut cmd = json!({ "id": id, "action": "nth", "selector": value, "index": 0, "subaction": subaction });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "last" => {
-                    let mut cmd = json!({ "id": id, "action": "nth", "selector": value, "index": -1, "subaction": subaction });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                _ => unreachable!(),
-            }
+pub fn parse_mouse(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    match inv.name {
+        "move" => {
+            let x = inv.number::<i32>(0, "x coordinate")?;
+            let y = inv.number::<i32>(1, "y coordinate")?;
+            Ok(json!({ "id": inv.id, "action": "mousemove", "x": x, "y": y }))
         }
-        "nth" => {
-            let idx_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "find nth".to_string(),
-                usage: "find nth <index> <selector> [action] [text]",
-            })?;
-            let idx = idx_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "find nth".to_string(),
-                    usage: "find nth <index> <selector> [action] [text]",
-                })?;
-            let sel = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "find nth".to_string(),
-                usage: "find nth <index> <selector> [action] [text]",
-            })?;
-            let sub = rest.get(3).unwrap_or(&"click");
-            let fv = if rest.len() > 4 {
-                Some(rest[4..].join(" "))
-            } else {
-                None
-            };
-            let mut cmd = json!({ "id": id, "action": "nth", "selector": sel, "index": idx, "subaction": sub });
-            if let Some(v) = fv {
-                cmd["value"] = json!(v);
-            }
+        "down" | "up" => {
+            let mut cmd = inv.request(&format!("mouse{}", inv.name));
+            cmd["button"] = json!(inv.arg(0).unwrap_or("left"));
             Ok(cmd)
         }
-        _ => Err(ParseError::UnknownSubcommand {
-            subcommand: locator.to_string(),
-            valid_options: VALID,
-        }),
-    }
-}
-
-fn parse_mouse(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["move", "down", "up", "wheel"];
-
-    match rest.get(0).map(|s| *s) {
-        Some("move") => {
-            let x_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "mouse move".to_string(),
-                usage: "mouse move <x> <y>",
-            })?;
-            let y_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "mouse move".to_string(),
-                usage: "mouse move <x> <y>",
-            })?;
-            let x = x_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "mouse move".to_string(),
-                    usage: "mouse move <x> <y>",
-                })?;
-            let y = y_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "mouse move".to_string(),
-                    usage: "mouse move <x> <y>",
-                })?;
-            Ok(json!({ "id": id, "action": "mousemove", "x": x, "y": y }))
-        }
-        Some("down") => {
-            Ok(json!({ "id": id, "action": "mousedown", "button": rest.get(1).unwrap_or(&"left") }))
-        }
-        Some("up") => {
-            Ok(json!({ "id": id, "action": "mouseup", "button": rest.get(1).unwrap_or(&"left") }))
-        }
-        Some("wheel") => {
-            let dy = rest
-                .get(1)
+        _ => {
+            let dy = inv
+                .arg(0)
                 .and_then(|s| s.parse::<i32>().ok())
                 .unwrap_or(100);
-            let dx = rest.get(2).and_then(|s| s.parse::<i32>().ok()).unwrap_or(0);
-            Ok(json!({ "id": id, "action": "wheel", "deltaX": dx, "deltaY": dy }))
+            let dx = inv.arg(1).and_then(|s| s.parse::<i32>().ok()).unwrap_or(0);
+            Ok(json!({ "id": inv.id, "action": "wheel", "deltaX": dx, "deltaY": dy }))
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "mouse".to_string(),
-            usage: "mouse <move|down|up|wheel> [args...]",
-        }),
     }
 }
 
-fn parse_set(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &[
-        "viewport",
-        "device",
-        "geo",
-        "geolocation",
-        "offline",
-        "headers",
-        "credentials",
-        "auth",
-        "media",
-    ];
-
-    match rest.get(0).map(|s| *s) {
-        Some("viewport") => {
-            let w_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set viewport".to_string(),
-                usage: "set viewport <width> <height>",
-            })?;
-            let h_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "set viewport".to_string(),
-                usage: "set viewport <width> <height>",
-            })?;
-            let w = w_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set viewport".to_string(),
-                    usage: "set viewport <width> <height>",
-                })?;
-            let h = h_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set viewport".to_string(),
-                    usage: "set viewport <width> <height>",
-                })?;
-            Ok(json!({ "id": id, "action": "viewport", "width": w, "height": h }))
-        }
-        Some("device") => {
-            let dev = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set device".to_string(),
-                usage: "set device <name>",
-            })?;
-            Ok(json!({ "id": id, "action": "device", "device": dev }))
-        }
-        Some("geo") | Some("geolocation") => {
-            let lat_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set geo".to_string(),
-                usage: "set geo <latitude> <longitude>",
-            })?;
-            let lng_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "set geo".to_string(),
-                usage: "set geo <latitude> <longitude>",
-            })?;
-            let lat = lat_str
-                .parse::<f64>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set geo".to_string(),
-                    usage: "set geo <latitude> <longitude>",
-                })?;
-            let lng = lng_str
-                .parse::<f64>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set geo".to_string(),
-                    usage: "set geo <latitude> <longitude>",
-                })?;
-            Ok(json!({ "id": id, "action": "geolocation", "latitude": lat, "longitude": lng }))
-        }
-        Some("offline") => {
-            let off = rest
-                .get(1)
-                .map(|s| *s != "off" && *s != "false")
+pub fn parse_set(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    match inv.name {
+        "viewport" => {
+            let w = inv.number::<i32>(0, "width")?;
+            let h = inv.number::<i32>(1, "height")?;
+            Ok(json!({ "id": inv.id, "action": "viewport", "width": w, "height": h }))
+        }
+        "device" => Ok(json!({ "id": inv.id, "action": "device", "device": inv.args[0] })),
+        "geo" => {
+            let lat = inv.number::<f64>(0, "latitude")?;
+            let lng = inv.number::<f64>(1, "longitude")?;
+            Ok(json!({ "id": inv.id, "action": "geolocation", "latitude": lat, "longitude": lng }))
+        }
+        "offline" => {
+            let off = inv
+                .arg(0)
+                .map(|s| s != "off" && s != "false")
                 .unwrap_or(true);
-            Ok(json!({ "id": id, "action": "offline", "offline": off }))
+            Ok(json!({ "id": inv.id, "action": "offline", "offline": off }))
         }
-        Some("headers") => {
-            let headers_json = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set headers".to_string(),
-                usage: "set headers <json>",
-            })?;
+        "headers" => {
             // Parse the JSON string into an object
-            let headers: serde_json::Value =
-                serde_json::from_str(headers_json).map_err(|_| ParseError::MissingArguments {
-                    context: "set headers".to_string(),
-                    usage: "set headers <json> (must be valid JSON object)",
-                })?;
-            Ok(json!({ "id": id, "action": "headers", "headers": headers }))
-        }
-        Some("credentials") | Some("auth") => {
-            let user = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set credentials".to_string(),
-                usage: "set credentials <username> <password>",
-            })?;
-            let pass = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "set credentials".to_string(),
-                usage: "set credentials <username> <password>",
-            })?;
-            Ok(json!({ "id": id, "action": "credentials", "username": user, "password": pass }))
-        }
-        Some("media") => {
-            let color = if rest.iter().any(|&s| s == "dark") {
+            let headers: serde_json::Value = serde_json::from_str(inv.args[0])
+                .map_err(|_| inv.invalid("Headers must be a valid JSON object".to_string()))?;
+            Ok(json!({ "id": inv.id, "action": "headers", "headers": headers }))
+        }
+        "credentials" => Ok(
+            json!({ "id": inv.id, "action": "credentials", "username": inv.args[0], "password": inv.args[1] }),
+        ),
+        _ => {
+            let color = if inv.args.contains(&"dark") {
                 "dark"
-            } else if rest.iter().any(|&s| s == "light") {
+            } else if inv.args.contains(&"light") {
                 "light"
             } else {
                 "no-preference"
             };
-            let reduced = if rest.iter().any(|&s| s == "reduced-motion") {
+            let reduced = if inv.args.contains(&"reduced-motion") {
                 "reduce"
             } else {
                 "no-preference"
             };
             Ok(
-                json!({ "id": id, "action": "emulatemedia", "colorScheme": color, "reducedMotion": reduced }),
+                json!({ "id": inv.id, "action": "emulatemedia", "colorScheme": color, "reducedMotion": reduced }),
             )
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "set".to_string(),
-            usage: "set <viewport|device|geo|offline|headers|credentials|media> [args...]",
-        }),
     }
 }
 
-fn parse_network(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["route", "unroute", "requests"];
-
-    match rest.get(0).map(|s| *s) {
-        Some("route") => {
-            let url = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "network route".to_string(),
-                usage: "network route <url> [--abort|--body <json>]",
-            })?;
-            let abort = rest.iter().any(|&s| s == "--abort");
-            let body_idx = rest.iter().position(|&s| s == "--body");
-            let body = body_idx.and_then(|i| rest.get(i + 1).map(|s| *s));
-            Ok(json!({ "id": id, "action": "route", "url": url, "abort": abort, "body": body }))
+pub fn parse_network(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    match inv.name {
+        "route" => {
+            let abort = inv.has("--abort");
+            let body = inv.value("--body");
+            Ok(
+                json!({ "id": inv.id, "action": "route", "url": inv.args[0], "abort": abort, "body": body }),
+            )
         }
-        Some("unroute") => {
-            let mut cmd = json!({ "id": id, "action": "unroute" });
-            if let Some(url) = rest.get(1) {
+        "unroute" => {
+            let mut cmd = inv.request("unroute");
+            if let Some(url) = inv.arg(0) {
                 cmd["url"] = json!(url);
             }
             Ok(cmd)
         }
-        Some("requests") => {
-            let clear = rest.iter().any(|&s| s == "--clear");
-            let filter_idx = rest.iter().position(|&s| s == "--filter");
-            let filter = filter_idx.and_then(|i| rest.get(i + 1).map(|s| *s));
-            let mut cmd = json!({ "id": id, "action": "requests", "clear": clear });
-            if let Some(f) = filter {
-                cmd["filter"] = json!(f);
+        _ => {
+            let mut cmd = inv.request("requests");
+            cmd["clear"] = json!(inv.has("--clear"));
+            if let Some(filter) = inv.value("--filter") {
+                cmd["filter"] = json!(filter);
             }
             Ok(cmd)
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "network".to_string(),
-            usage: "network <route|unroute|requests> [args...]",
+    }
+}
+
+pub fn parse_storage(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let storage_type = inv.name;
+    let key = inv.arg(1);
+    match inv.arg(0).unwrap_or("get") {
+        "get" => {
+            let mut cmd = json!({ "id": inv.id, "action": "storage_get", "type": storage_type });
+            if let Some(k) = key {
+                cmd["key"] = json!(k);
+            }
+            Ok(cmd)
+        }
+        "set" => match (key, inv.arg(2)) {
+            (Some(k), Some(v)) => Ok(
+                json!({ "id": inv.id, "action": "storage_set", "type": storage_type, "key": k, "value": v }),
+            ),
+            _ => Err(ParseError::MissingArguments {
+                context: format!("storage {} set", storage_type),
+                usage: "storage <local|session> set <key> <value>",
+            }),
+        },
+        "clear" => Ok(json!({ "id": inv.id, "action": "storage_clear", "type": storage_type })),
+        op => Err(ParseError::UnknownSubcommand {
+            subcommand: op.to_string(),
+            valid_options: vec!["get", "set", "clear"],
         }),
     }
 }
 
-fn parse_storage(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["local", "session"];
-
-    match rest.get(0).map(|s| *s) {
-        Some("local") | Some("session") => {
-            let storage_type = rest.get(0).unwrap();
-            let op = rest.get(1).unwrap_or(&"get");
-            let key = rest.get(2);
-            let value = rest.get(3);
-            match *op {
-                "set" => {
-                    let k = key.ok_or_else(|| ParseError::MissingArguments {
-                        context: format!("storage {} set", storage_type),
-                        usage: "storage <local|session> set <key> <value>",
-                    })?;
-                    let v = value.ok_or_else(|| ParseError::MissingArguments {
-                        context: format!("storage {} set", storage_type),
-                        usage: "storage <local|session> set <key> <value>",
-                    })?;
-                    Ok(
-                        json!({ "id": id, "action": "storage_set", "type": storage_type, "key": k, "value": v }),
-                    )
+pub fn parse_cookies(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    match inv.name {
+        "set" => {
+            let mut cookie = json!({ "name": inv.args[0], "value": inv.args[1] });
+            for (flag, field) in [("--url", "url"), ("--domain", "domain"), ("--path", "path")] {
+                if let Some(value) = inv.value(flag) {
+                    cookie[field] = json!(value);
+                }
+            }
+            for (flag, field) in [("--httpOnly", "httpOnly"), ("--secure", "secure")] {
+                if inv.has(flag) {
+                    cookie[field] = json!(true);
                 }
-                "clear" => Ok(json!({ "id": id, "action": "storage_clear", "type": storage_type })),
-                _ => {
-                    let mut cmd =
-                        json!({ "id": id, "action": "storage_get", "type": storage_type });
-                    if let Some(k) = key {
-                        cmd.as_object_mut()
-                            .unwrap()
-                            .insert("key".to_string(), json!(k));
-                    }
-                    Ok(cmd)
+            }
+            if let Some(same_site) = inv.value("--sameSite") {
+                if !["Strict", "Lax", "None"].contains(&same_site) {
+                    return Err(inv.invalid(format!(
+                        "Invalid --sameSite: {} (use Strict, Lax or None)",
+                        same_site
+                    )));
                 }
+                cookie["sameSite"] = json!(same_site);
+            }
+            if let Some(expires) = inv.value("--expires") {
+                let expires: i64 = expires
+                    .parse()
+                    .map_err(|_| inv.invalid(format!("Invalid --expires: {}", expires)))?;
+                cookie["expires"] = json!(expires);
             }
+            Ok(json!({ "id": inv.id, "action": "cookies_set", "cookies": [cookie] }))
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "storage".to_string(),
-            usage: "storage <local|session> [get|set|clear] [key] [value]",
-        }),
+        "clear" => Ok(inv.request("cookies_clear")),
+        _ => Ok(inv.request("cookies_get")),
     }
 }
 
+pub fn parse_tab(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    match inv.name {
+        "new" => {
+            let mut cmd = inv.request("tab_new");
+            if let Some(url) = inv.arg(0) {
+                cmd["url"] = json!(url);
+            }
+            Ok(cmd)
+        }
+        "list" => Ok(inv.request("tab_list")),
+        "close" => {
+            let mut cmd = inv.request("tab_close");
+            if let Some(index) = inv.arg(0).and_then(|s| s.parse::<i32>().ok()) {
+                cmd["index"] = json!(index);
+            }
+            Ok(cmd)
+        }
+        // `tab <index>`
+        _ => match inv.args[0].parse::<i32>() {
+            Ok(index) => Ok(json!({ "id": inv.id, "action": "tab_switch", "index": index })),
+            Err(_) => Err(ParseError::UnknownSubcommand {
+                subcommand: inv.args[0].to_string(),
+                valid_options: registry::next_words("tab"),
+            }),
+        },
+    }
+}
+
+pub fn parse_window(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    Ok(inv.request("window_new"))
+}
+
+pub fn parse_frame(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    match inv.args[0] {
+        "main" => Ok(inv.request("mainframe")),
+        selector => Ok(json!({ "id": inv.id, "action": "frame", "selector": selector })),
+    }
+}
+
+pub fn parse_dialog(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let mut cmd = json!({ "id": inv.id, "action": "dialog", "response": inv.name });
+    if let (Some(prompt_text), "accept") = (inv.arg(0), inv.name) {
+        cmd["promptText"] = json!(prompt_text);
+    }
+    Ok(cmd)
+}
+
+pub fn parse_trace(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    match inv.name {
+        "start" => Ok(inv.request("trace_start")),
+        _ => Ok(json!({ "id": inv.id, "action": "trace_stop", "path": inv.args[0] })),
+    }
+}
+
+/// Playwright native video recording
+pub fn parse_record(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    if inv.name == "stop" {
+        return Ok(inv.request("recording_stop"));
+    }
+    let mut cmd = inv.request(&format!("recording_{}", inv.name));
+    cmd["path"] = json!(inv.args[0]);
+    if let Some(url) = inv.arg(1) {
+        // Add https:// prefix if needed
+        let url = if url.starts_with("http") {
+            url.to_string()
+        } else {
+            format!("https://{}", url)
+        };
+        cmd["url"] = json!(url);
+    }
+    Ok(cmd)
+}
+
+/// `console` and `errors`
+pub fn parse_console(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let mut cmd = inv.request(inv.name);
+    cmd["clear"] = json!(inv.has("--clear"));
+    Ok(cmd)
+}
+
+pub fn parse_events(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    // `--until` is handled client-side by `events::run_events`, and `--timeout`
+    // is a global flag, stripped before parsing
+    let mut kinds: Vec<&str> = Vec::new();
+    for kind in inv.args.iter().flat_map(|arg| arg.split(',')) {
+        if kind.is_empty() {
+            continue;
+        }
+        // Accept the plural spelling used by the `errors` command
+        let kind = if kind == "errors" { "error" } else { kind };
+        if !EVENT_KINDS.contains(&kind) {
+            return Err(ParseError::UnknownSubcommand {
+                subcommand: kind.to_string(),
+                valid_options: EVENT_KINDS.to_vec(),
+            });
+        }
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+
+    let mut cmd = json!({ "id": inv.id, "action": "events", "follow": inv.has("--follow") });
+    if !kinds.is_empty() {
+        cmd["kinds"] = json!(kinds);
+    }
+    Ok(cmd)
+}
+
+pub fn parse_state(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let mut cmd = inv.request(&format!("state_{}", inv.name));
+    cmd["path"] = json!(inv.args[0]);
+    Ok(cmd)
+}
+
+pub fn parse_swipe(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    let direction = inv.args[0];
+    if !["up", "down", "left", "right"].contains(&direction) {
+        return Err(inv.invalid(format!("Invalid swipe direction: {}", direction)));
+    }
+    let mut cmd = json!({ "id": inv.id, "action": "swipe", "direction": direction });
+    if let Some(distance) = inv.arg(1).and_then(|s| s.parse::<u32>().ok()) {
+        cmd["distance"] = json!(distance);
+    }
+    Ok(cmd)
+}
+
+/// List available iOS simulators
+pub fn parse_device(inv: &Invocation, _: &Flags) -> Result<Value, ParseError> {
+    Ok(inv.request("device_list"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1415,6 +930,8 @@ mod tests {
             full: false,
             headed: false,
             debug: false,
+            stealth: false,
+            stealth_options: None,
             headers: None,
             executable_path: None,
             extensions: Vec::new(),
@@ -1429,15 +946,18 @@ mod tests {
             ignore_https_errors: false,
             allow_file_access: false,
             device: None,
-            cli_executable_path: false,
-            cli_extensions: false,
-            cli_profile: false,
-            cli_state: false,
-            cli_args: false,
-            cli_user_agent: false,
-            cli_proxy: false,
-            cli_proxy_bypass: false,
-            cli_allow_file_access: false,
+            timeout: None,
+            retries: None,
+            retry_backoff: None,
+            daemon_url: None,
+            daemon_token: None,
+            log_file: None,
+            node_path: None,
+            node_args: None,
+            idle_timeout: None,
+            restart_on_change: false,
+            preset: None,
+            sources: Default::default(),
         }
     }
 
@@ -1445,6 +965,20 @@ mod tests {
         s.split_whitespace().map(String::from).collect()
     }
 
+    // === Request ID Tests ===
+
+    #[test]
+    fn test_gen_id_unique() {
+        let ids: std::collections::HashSet<String> = (0..1000).map(|_| gen_id()).collect();
+        assert_eq!(ids.len(), 1000);
+    }
+
+    #[test]
+    fn test_gen_id_includes_pid() {
+        let id = gen_id();
+        assert!(id.starts_with(&format!("r{}-", std::process::id())));
+    }
+
     // === Cookies Tests ===
 
     #[test]
@@ -1872,6 +1406,32 @@ mod tests {
         assert_eq!(cmd["action"], "tab_close");
     }
 
+    #[test]
+    fn test_tab_unknown_subcommand() {
+        let result = parse_command(&args("tab foo"), &default_flags());
+        assert!(matches!(
+            result,
+            Err(ParseError::UnknownSubcommand { ref subcommand, .. }) if subcommand == "foo"
+        ));
+    }
+
+    // === Dialog ===
+
+    #[test]
+    fn test_dialog_accept_with_text() {
+        let cmd = parse_command(&args("dialog accept hello"), &default_flags()).unwrap();
+        assert_eq!(cmd["response"], "accept");
+        assert_eq!(cmd["promptText"], "hello");
+    }
+
+    #[test]
+    fn test_dialog_dismiss() {
+        let cmd = parse_command(&args("dialog dismiss"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "dialog");
+        assert_eq!(cmd["response"], "dismiss");
+        assert!(cmd.get("promptText").is_none());
+    }
+
     // === Screenshot ===
 
     #[test]
@@ -2158,12 +1718,21 @@ mod tests {
     #[test]
     fn test_eval_base64_long_flag() {
         // "document.title" in base64
-        let cmd =
-            parse_command(&args("eval --base64 ZG9jdW1lbnQudGl0bGU="), &default_flags()).unwrap();
+        let cmd = parse_command(
+            &args("eval --base64 ZG9jdW1lbnQudGl0bGU="),
+            &default_flags(),
+        )
+        .unwrap();
         assert_eq!(cmd["action"], "evaluate");
         assert_eq!(cmd["script"], "document.title");
     }
 
+    #[test]
+    fn test_eval_flags_not_bound_after_end_of_options() {
+        let cmd = parse_command(&args("eval -- -b"), &default_flags()).unwrap();
+        assert_eq!(cmd["script"], "-b");
+    }
+
     #[test]
     fn test_eval_base64_with_special_chars() {
         // "document.querySelector('[src*=\"_next\"]')" in base64
@@ -2463,4 +2032,82 @@ mod tests {
         assert_eq!(cmd["action"], "launch");
         assert_eq!(cmd["cdpPort"], 1);
     }
+
+    // === Events Tests ===
+
+    #[test]
+    fn test_events_defaults() {
+        let cmd = parse_command(&args("events"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "events");
+        assert_eq!(cmd["follow"], false);
+        assert!(cmd.get("kinds").is_none());
+    }
+
+    #[test]
+    fn test_events_follow_with_kinds() {
+        let cmd = parse_command(
+            &args("events console,errors dialog --follow"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["follow"], true);
+        assert_eq!(cmd["kinds"], json!(["console", "error", "dialog"]));
+    }
+
+    #[test]
+    fn test_events_skips_client_side_options() {
+        let cmd = parse_command(
+            &args("events request --follow --until checkout"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["kinds"], json!(["request"]));
+    }
+
+    #[test]
+    fn test_events_until_requires_value() {
+        let result = parse_command(&args("events --follow --until"), &default_flags());
+        assert!(matches!(result, Err(ParseError::MissingArguments { .. })));
+    }
+
+    #[test]
+    fn test_events_unknown_kind() {
+        let result = parse_command(&args("events console,frames"), &default_flags());
+        assert!(matches!(
+            result,
+            Err(ParseError::UnknownSubcommand { ref subcommand, .. }) if subcommand == "frames"
+        ));
+    }
+
+    // === Registry-driven parsing ===
+
+    #[test]
+    fn test_flags_do_not_leak_into_arguments() {
+        let cmd = parse_command(
+            &args("find label Email fill me@example.com --exact"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["value"], "me@example.com");
+        assert_eq!(cmd["exact"], true);
+    }
+
+    #[test]
+    fn test_subcommand_alias_resolves() {
+        let cmd = parse_command(&args("set auth admin secret"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "credentials");
+    }
+
+    #[test]
+    fn test_local_command_rejected() {
+        let result = parse_command(&args("session list"), &default_flags());
+        assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn test_missing_flag_value_uses_command_usage() {
+        let err = parse_command(&args("snapshot --depth"), &default_flags()).unwrap_err();
+        assert!(err.format().contains("snapshot --depth"));
+        assert!(err.format().contains("Usage: agent-browser snapshot"));
+    }
 }
@@ -0,0 +1,575 @@
+//! `agent-browser completions <bash|zsh|fish>`: shell completion scripts.
+//!
+//! The scripts are generated from the command registry and `GLOBAL_FLAGS`, the
+//! same tables the parser uses, so they never offer a command or flag the CLI
+//! would reject. Session names are completed at
+//! runtime through `agent-browser completions sessions`, which lists the live
+//! daemons' `.pid` files in the socket directory.
+
+use crate::commands::ParseError;
+use crate::flags::{FlagSpec, Flags, GLOBAL_FLAGS};
+use crate::output::print_parse_error;
+use crate::registry::{self, REGISTRY};
+use crate::session::local_sessions;
+
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// Global flags whose value is a file or directory
+fn is_path_flag(spec: &FlagSpec) -> bool {
+    spec.value == Some("<path>")
+}
+
+fn names<'a>(specs: impl IntoIterator<Item = &'a FlagSpec>) -> Vec<&'static str> {
+    specs
+        .into_iter()
+        .flat_map(|spec| spec.names)
+        .copied()
+        .collect()
+}
+
+/// Commands that take a subcommand (or one of a few words) first
+fn commands_with_subcommands() -> Vec<&'static str> {
+    registry::command_names()
+        .into_iter()
+        .filter(|cmd| !registry::next_words(cmd).is_empty())
+        .collect()
+}
+
+/// Each command that has flags of its own, with those flags
+fn command_flags() -> Vec<(&'static str, Vec<&'static FlagSpec>)> {
+    REGISTRY
+        .iter()
+        .map(|cmd| (cmd.name, registry::flags_of(cmd)))
+        .filter(|(_, specs)| !specs.is_empty())
+        .collect()
+}
+
+/// `case` arms (shared by bash and zsh) telling whether a flag takes a value.
+/// Command flags come first, since after the command they shadow global ones.
+fn takes_value_arms() -> String {
+    let mut arms = String::new();
+    let mut arm = |patterns: Vec<String>, status: u8| {
+        if !patterns.is_empty() {
+            arms.push_str(&format!(
+                "        {}) return {} ;;\n",
+                patterns.join(" | "),
+                status
+            ));
+        }
+    };
+    for (cmd, specs) in command_flags() {
+        let (valued, switches): (Vec<&FlagSpec>, Vec<&FlagSpec>) =
+            specs.into_iter().partition(|spec| spec.value.is_some());
+        let patterns = |specs: Vec<&FlagSpec>| -> Vec<String> {
+            names(specs)
+                .iter()
+                .map(|name| format!("{}:{}", cmd, name))
+                .collect()
+        };
+        arm(patterns(valued), 0);
+        arm(patterns(switches), 1);
+    }
+    arm(
+        names(GLOBAL_FLAGS.iter().filter(|spec| spec.value.is_some()))
+            .iter()
+            .map(|name| format!("*:{}", name))
+            .collect(),
+        0,
+    );
+    arms
+}
+
+/// `case` arms listing each command's own flags
+fn command_flag_arms() -> String {
+    command_flags()
+        .into_iter()
+        .map(|(cmd, specs)| format!("        {}) echo \"{}\" ;;\n", cmd, names(specs).join(" ")))
+        .collect()
+}
+
+/// `case` arms listing each command's subcommands
+fn subcommand_arms() -> String {
+    commands_with_subcommands()
+        .iter()
+        .map(|cmd| {
+            format!(
+                "        {}) echo \"{}\" ;;\n",
+                cmd,
+                registry::next_words(cmd).join(" ")
+            )
+        })
+        .collect()
+}
+
+fn path_flag_pattern() -> String {
+    names(GLOBAL_FLAGS.iter().filter(|spec| is_path_flag(spec))).join(" | ")
+}
+
+/// Helpers shared by the bash and zsh scripts
+const SHARED_HELPERS: &str = r#"_agent_browser_takes_value() {
+    case "$1:$2" in
+@TAKES_VALUE@    esac
+    return 1
+}
+
+_agent_browser_command_flags() {
+    case "$1" in
+@COMMAND_FLAGS@    esac
+}
+
+_agent_browser_subcommands() {
+    case "$1" in
+@SUBCOMMANDS@    esac
+}
+
+_agent_browser_sessions() {
+    agent-browser completions sessions 2>/dev/null
+}
+"#;
+
+const BASH_SCRIPT: &str = r#"# bash completion for agent-browser
+#
+# Load it in the current shell with:
+#   source <(agent-browser completions bash)
+# or install it for every shell:
+#   agent-browser completions bash > ~/.local/share/bash-completion/completions/agent-browser
+
+@SHARED@
+_agent_browser() {
+    local cur=${COMP_WORDS[COMP_CWORD]} prev=${COMP_WORDS[COMP_CWORD-1]}
+    local cmd="" sub="" word i
+
+    # The command and subcommand so far, skipping flags and their values
+    # (bash splits --flag=value into three words)
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        word=${COMP_WORDS[i]}
+        [[ $word == -- ]] && break
+        if [[ $word == -* ]]; then
+            if _agent_browser_takes_value "$cmd" "$word"; then
+                ((i++))
+                [[ ${COMP_WORDS[i]} == = ]] && ((i++))
+            fi
+            continue
+        fi
+        if [[ -z $cmd ]]; then
+            cmd=$word
+        elif [[ -z $sub ]]; then
+            sub=$word
+        fi
+    done
+
+    local flag=$prev value=$cur
+    if [[ $cur == = ]]; then
+        value=""
+    elif [[ $prev == = ]]; then
+        flag=${COMP_WORDS[COMP_CWORD-2]}
+    fi
+    if [[ $flag == -* ]] && _agent_browser_takes_value "$cmd" "$flag"; then
+        case $flag in
+            --session) COMPREPLY=($(compgen -W "$(_agent_browser_sessions)" -- "$value")) ;;
+            @PATH_FLAGS@)
+                compopt -o filenames 2>/dev/null
+                COMPREPLY=($(compgen -f -- "$value"))
+                ;;
+        esac
+        return
+    fi
+
+    if [[ $cur == -* ]]; then
+        COMPREPLY=($(compgen -W "@GLOBAL_FLAGS@ $(_agent_browser_command_flags "$cmd")" -- "$cur"))
+        return
+    fi
+    if [[ -z $cmd ]]; then
+        COMPREPLY=($(compgen -W "@COMMANDS@" -- "$cur"))
+        return
+    fi
+    if [[ $cmd == session && ($sub == kill || $sub == clone) ]]; then
+        COMPREPLY=($(compgen -W "$(_agent_browser_sessions)" -- "$cur"))
+        return
+    fi
+    local subcommands=$(_agent_browser_subcommands "$cmd")
+    if [[ -n $subcommands ]]; then
+        [[ -z $sub ]] && COMPREPLY=($(compgen -W "$subcommands" -- "$cur"))
+        return
+    fi
+    compopt -o filenames 2>/dev/null
+    COMPREPLY=($(compgen -f -- "$cur"))
+}
+
+complete -F _agent_browser agent-browser
+"#;
+
+const ZSH_SCRIPT: &str = r#"#compdef agent-browser
+# zsh completion for agent-browser
+#
+# Load it in the current shell (after compinit) with:
+#   source <(agent-browser completions zsh)
+# or install it as _agent-browser in a directory on $fpath:
+#   agent-browser completions zsh > "${fpath[1]}/_agent-browser"
+
+@SHARED@
+_agent_browser() {
+    local cur=${words[CURRENT]} prev=${words[CURRENT-1]}
+    local cmd="" sub="" word i
+    local -a candidates
+
+    # The command and subcommand so far, skipping flags and their values
+    for ((i = 2; i < CURRENT; i++)); do
+        word=${words[i]}
+        [[ $word == -- ]] && break
+        if [[ $word == -* ]]; then
+            [[ $word != *=* ]] && _agent_browser_takes_value "$cmd" "$word" && ((i++))
+            continue
+        fi
+        if [[ -z $cmd ]]; then
+            cmd=$word
+        elif [[ -z $sub ]]; then
+            sub=$word
+        fi
+    done
+
+    local flag=""
+    if [[ $cur == -*=* ]]; then
+        flag=${cur%%=*}
+        compset -P '*='
+    elif [[ $prev == -* ]]; then
+        flag=$prev
+    fi
+    if [[ -n $flag ]] && _agent_browser_takes_value "$cmd" "$flag"; then
+        case $flag in
+            --session)
+                candidates=(${(f)"$(_agent_browser_sessions)"})
+                compadd -a candidates
+                ;;
+            @PATH_FLAGS@) _files ;;
+        esac
+        return
+    fi
+
+    if [[ $cur == -* ]]; then
+        candidates=(@GLOBAL_FLAGS@ $(_agent_browser_command_flags "$cmd"))
+        compadd -a candidates
+        return
+    fi
+    if [[ -z $cmd ]]; then
+        candidates=(@COMMANDS@)
+        compadd -a candidates
+        return
+    fi
+    if [[ $cmd == session && ($sub == kill || $sub == clone) ]]; then
+        candidates=(${(f)"$(_agent_browser_sessions)"})
+        compadd -a candidates
+        return
+    fi
+    candidates=($(_agent_browser_subcommands "$cmd"))
+    if (( ${#candidates} )); then
+        [[ -z $sub ]] && compadd -a candidates
+        return
+    fi
+    _files
+}
+
+# Autoloaded from _agent-browser: complete now; sourced: register
+if [[ $funcstack[1] == _agent-browser ]]; then
+    _agent_browser "$@"
+else
+    compdef _agent_browser agent-browser
+fi
+"#;
+
+const FISH_SCRIPT: &str = r#"# fish completion for agent-browser
+#
+# Load it in the current shell with:
+#   agent-browser completions fish | source
+# or install it for every shell:
+#   agent-browser completions fish > ~/.config/fish/completions/agent-browser.fish
+
+function __agent_browser_takes_value
+    switch "$argv[1]:$argv[2]"
+@TAKES_VALUE@    end
+    return 1
+end
+
+# The command and subcommand so far, skipping flags and their values
+function __agent_browser_words
+    set -l tokens (commandline -opc)
+    set -l words
+    set -l i 2
+    while test $i -le (count $tokens)
+        set -l token $tokens[$i]
+        set i (math $i + 1)
+        if test "$token" = --
+            break
+        else if string match -q -- '-*' $token
+            if not string match -q -- '*=*' $token; and __agent_browser_takes_value "$words[1]" $token
+                set i (math $i + 1)
+            end
+        else
+            set -a words $token
+        end
+    end
+    string join \n -- $words
+end
+
+function __agent_browser_needs_command
+    set -l words (__agent_browser_words)
+    test (count $words) -eq 0
+end
+
+function __agent_browser_needs_subcommand
+    set -l words (__agent_browser_words)
+    test (count $words) -eq 1; and test "$words[1]" = "$argv[1]"
+end
+
+# True when the command is $argv[1] and, if more are given, the subcommand is one of them
+function __agent_browser_using
+    set -l words (__agent_browser_words)
+    test "$words[1]" = "$argv[1]"; or return 1
+    test (count $argv) -eq 1; and return 0
+    contains -- "$words[2]" $argv[2..-1]
+end
+
+# Plain arguments complete as files, except where a subcommand or session name goes
+function __agent_browser_takes_files
+    set -l words (__agent_browser_words)
+    set -q words[1]; or return 1
+    test "$words[1]" != session; or return 1
+    contains -- $words[1] @COMMANDS_WITH_SUBCOMMANDS@; or return 0
+    test (count $words) -ge 2
+end
+
+function __agent_browser_sessions
+    agent-browser completions sessions 2>/dev/null
+end
+
+complete -c agent-browser -f
+complete -c agent-browser -n __agent_browser_needs_command -a '@COMMANDS@'
+@SUBCOMMANDS@complete -c agent-browser -n '__agent_browser_using session kill clone' -a '(__agent_browser_sessions)'
+complete -c agent-browser -n __agent_browser_takes_files -F
+
+# Global flags
+@GLOBAL_FLAGS@
+# Command flags
+@COMMAND_FLAGS@"#;
+
+/// One flag's `complete` options: `-l long -s x` plus how its value completes
+fn fish_flag(spec: &FlagSpec) -> String {
+    let mut parts: Vec<String> = spec
+        .names
+        .iter()
+        .map(|name| match name.strip_prefix("--") {
+            Some(long) => format!("-l {}", long),
+            None => format!("-s {}", &name[1..]),
+        })
+        .collect();
+    match spec.value {
+        None => {}
+        Some(_) if spec.names[0] == "--session" => {
+            parts.push("-x -a '(__agent_browser_sessions)'".to_string())
+        }
+        Some(_) if is_path_flag(spec) => parts.push("-r -F".to_string()),
+        Some(_) => parts.push("-x".to_string()),
+    }
+    parts.join(" ")
+}
+
+fn fish_script() -> String {
+    let takes_value: String = command_flags()
+        .into_iter()
+        .flat_map(|(cmd, specs)| {
+            let (valued, switches): (Vec<&FlagSpec>, Vec<&FlagSpec>) =
+                specs.into_iter().partition(|spec| spec.value.is_some());
+            [(valued, 0), (switches, 1)]
+                .into_iter()
+                .filter(|(specs, _)| !specs.is_empty())
+                .map(move |(specs, status)| {
+                    let patterns: Vec<String> = names(specs)
+                        .iter()
+                        .map(|name| format!("'{}:{}'", cmd, name))
+                        .collect();
+                    format!(
+                        "        case {}\n            return {}\n",
+                        patterns.join(" "),
+                        status
+                    )
+                })
+        })
+        .chain(std::iter::once(format!(
+            "        case {}\n            return 0\n",
+            names(GLOBAL_FLAGS.iter().filter(|spec| spec.value.is_some()))
+                .iter()
+                .map(|name| format!("'*:{}'", name))
+                .collect::<Vec<_>>()
+                .join(" ")
+        )))
+        .collect();
+
+    let subcommand_lines: String = commands_with_subcommands()
+        .iter()
+        .map(|cmd| {
+            format!(
+                "complete -c agent-browser -n '__agent_browser_needs_subcommand {}' -a '{}'\n",
+                cmd,
+                registry::next_words(cmd).join(" ")
+            )
+        })
+        .collect();
+
+    let global_lines: String = GLOBAL_FLAGS
+        .iter()
+        .map(|spec| format!("complete -c agent-browser {}\n", fish_flag(spec)))
+        .collect();
+
+    let command_lines: String = command_flags()
+        .into_iter()
+        .flat_map(|(cmd, specs)| {
+            specs.into_iter().map(move |spec| {
+                format!(
+                    "complete -c agent-browser -n '__agent_browser_using {}' {}\n",
+                    cmd,
+                    fish_flag(spec)
+                )
+            })
+        })
+        .collect();
+
+    FISH_SCRIPT
+        .replace("@TAKES_VALUE@", &takes_value)
+        .replace(
+            "@COMMANDS_WITH_SUBCOMMANDS@",
+            &commands_with_subcommands().join(" "),
+        )
+        .replace("@COMMANDS@", &registry::command_names().join(" "))
+        .replace("@SUBCOMMANDS@", &subcommand_lines)
+        .replace("@GLOBAL_FLAGS@", &global_lines)
+        .replace("@COMMAND_FLAGS@", &command_lines)
+}
+
+/// The completion script for `shell`, or `None` if it isn't supported
+pub fn script(shell: &str) -> Option<String> {
+    let shared = || {
+        SHARED_HELPERS
+            .replace("@TAKES_VALUE@", &takes_value_arms())
+            .replace("@COMMAND_FLAGS@", &command_flag_arms())
+            .replace("@SUBCOMMANDS@", &subcommand_arms())
+    };
+    let template = match shell {
+        "bash" => BASH_SCRIPT,
+        "zsh" => ZSH_SCRIPT,
+        "fish" => return Some(fish_script()),
+        _ => return None,
+    };
+    Some(
+        template
+            .replace("@SHARED@", &shared())
+            .replace("@PATH_FLAGS@", &path_flag_pattern())
+            .replace("@GLOBAL_FLAGS@", &names(GLOBAL_FLAGS).join(" "))
+            .replace("@COMMANDS@", &registry::command_names().join(" ")),
+    )
+}
+
+/// Run `agent-browser completions <bash|zsh|fish>`. Returns the process exit code.
+///
+/// `completions sessions` is used by the scripts themselves: it prints the
+/// names of the live local sessions, one per line.
+pub fn run_completions(args: &[String], flags: &Flags) -> i32 {
+    let Some(shell) = args.get(1).map(|s| s.as_str()) else {
+        print_parse_error(
+            &ParseError::MissingArguments {
+                context: "completions".to_string(),
+                usage: "completions <bash|zsh|fish>",
+            },
+            flags.json,
+        );
+        return 1;
+    };
+
+    if shell == "sessions" {
+        let mut sessions = local_sessions();
+        sessions.sort();
+        for name in sessions {
+            println!("{}", name);
+        }
+        return 0;
+    }
+
+    match script(shell) {
+        Some(script) => {
+            print!("{}", script);
+            0
+        }
+        None => {
+            print_parse_error(
+                &ParseError::UnknownSubcommand {
+                    subcommand: shell.to_string(),
+                    valid_options: SHELLS.to_vec(),
+                },
+                flags.json,
+            );
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_unknown_shell() {
+        assert!(script("powershell").is_none());
+    }
+
+    #[test]
+    fn test_scripts_fully_expanded() {
+        for shell in SHELLS {
+            let script = script(shell).unwrap();
+            for placeholder in ["@SHARED@", "@COMMANDS@", "@GLOBAL_FLAGS@", "@PATH_FLAGS@"] {
+                assert!(
+                    !script.contains(placeholder),
+                    "{} has {}",
+                    shell,
+                    placeholder
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_bash_script_from_tables() {
+        let script = script("bash").unwrap();
+        assert!(script.contains("complete -F _agent_browser agent-browser"));
+        assert!(script.contains(&registry::command_names().join(" ")));
+        assert!(script
+            .contains("        get) echo \"text html value attr url title count box styles\" ;;"));
+        assert!(script.contains("        snapshot) echo \"--interactive -i --compact -c --cursor -C --depth -d --selector -s\" ;;"));
+        // Command flags shadow global ones: `wait -f` takes a value
+        let wait = script.find("wait:--fn | wait:-f").unwrap();
+        let global = script.find("*:--session").unwrap();
+        assert!(wait < global);
+        assert!(!script.contains("*:--full"));
+    }
+
+    #[test]
+    fn test_zsh_script_autoloads_or_registers() {
+        let script = script("zsh").unwrap();
+        assert!(script.starts_with("#compdef agent-browser\n"));
+        assert!(script.contains("compdef _agent_browser agent-browser"));
+        assert!(script.contains("--executable-path | --extension | --profile | --state | --log-file | --node-path) _files ;;"));
+    }
+
+    #[test]
+    fn test_fish_flags() {
+        let script = script("fish").unwrap();
+        assert!(script.contains("complete -c agent-browser -l full -s f\n"));
+        assert!(script
+            .contains("complete -c agent-browser -l session -x -a '(__agent_browser_sessions)'\n"));
+        assert!(script.contains("complete -c agent-browser -l profile -r -F\n"));
+        assert!(script.contains("complete -c agent-browser -l timeout -x\n"));
+        assert!(script
+            .contains("complete -c agent-browser -n '__agent_browser_using wait' -l fn -s f -x\n"));
+        assert!(script.contains(
+            "complete -c agent-browser -n '__agent_browser_needs_subcommand dialog' -a 'accept dismiss'\n"
+        ));
+    }
+}
@@ -1,17 +1,31 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
 use std::env;
+use std::fmt;
 use std::fs;
-use std::io::{BufRead, BufReader, Read, Write};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::net::TcpStream;
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Mutex, OnceLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 #[cfg(unix)]
 use std::os::unix::net::UnixStream;
 
+#[cfg(windows)]
+use windows_sys::Win32::Foundation::CloseHandle;
+#[cfg(windows)]
+use windows_sys::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+use crate::daemon::mask_proxy;
+use crate::debug;
+use crate::flags::Flags;
+use crate::launch::LaunchOptions;
+use crate::runtime::resolve_runtime;
+
 #[derive(Serialize)]
 #[allow(dead_code)]
 pub struct Request {
@@ -23,12 +37,14 @@ pub struct Request {
 
 #[derive(Deserialize, Serialize, Default)]
 pub struct Response {
+    /// Id of the request this response answers (used for correlation, not printed)
+    #[serde(default, skip_serializing)]
+    pub id: Option<String>,
     pub success: bool,
     pub data: Option<Value>,
     pub error: Option<String>,
 }
 
-#[allow(dead_code)]
 pub enum Connection {
     #[cfg(unix)]
     Unix(UnixStream),
@@ -79,6 +95,570 @@ impl Connection {
             Connection::Tcp(s) => s.set_write_timeout(dur),
         }
     }
+
+    pub fn try_clone(&self) -> std::io::Result<Connection> {
+        match self {
+            #[cfg(unix)]
+            Connection::Unix(s) => s.try_clone().map(Connection::Unix),
+            Connection::Tcp(s) => s.try_clone().map(Connection::Tcp),
+        }
+    }
+}
+
+/// Version of this CLI, which must match the daemon's (both come from package.json)
+pub const CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Socket read timeout when neither the request nor `--timeout` sets one
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Actions whose daemon request schema has a `timeout` field (src/protocol.ts)
+const TIMEOUT_ACTIONS: &[&str] = &[
+    "navigate",
+    "evaluate",
+    "wait",
+    "waitforurl",
+    "waitforloadstate",
+    "waitforfunction",
+    "waitfordownload",
+    "responsebody",
+];
+/// Extra time the socket waits past a request's timeout, so the daemon can
+/// report its own (more specific) timeout error first
+const TIMEOUT_GRACE: Duration = Duration::from_millis(500);
+
+/// Failure talking to a session daemon.
+///
+/// Built from `io::ErrorKind` and serde errors rather than message text, so
+/// retries and exit codes don't depend on platform or locale.
+#[derive(Debug)]
+pub enum ConnectionError {
+    /// No daemon is listening: the socket is missing or the connection was refused
+    NotRunning(io::Error),
+    /// The daemon is alive but not accepting or reading requests right now
+    Busy(io::Error),
+    /// The daemon closed the connection (or crashed) before replying
+    Disconnected(String),
+    /// No reply within the request's timeout
+    Timeout { action: String, ms: u64 },
+    /// The daemon sent something that isn't a valid response
+    InvalidResponse(serde_json::Error),
+    /// The request itself could not be sent (e.g. missing id)
+    InvalidRequest(String),
+    /// A remote daemon (`--daemon-url`) rejected the handshake token
+    AuthFailed(String),
+    /// The daemon was built from a different release than this CLI
+    /// (`None` if it is too old to answer the version handshake)
+    VersionMismatch { daemon: Option<String> },
+    /// Any other I/O failure
+    Io(io::Error),
+    /// A transient error persisted through every retry
+    RetriesExhausted {
+        attempts: u32,
+        last: Box<ConnectionError>,
+    },
+}
+
+impl ConnectionError {
+    /// Classify an I/O error from an established connection
+    fn from_io(e: io::Error) -> ConnectionError {
+        match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => ConnectionError::Busy(e),
+            io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => ConnectionError::Disconnected(e.to_string()),
+            _ => ConnectionError::Io(e),
+        }
+    }
+
+    /// Classify an I/O error from opening the connection
+    fn from_connect(e: io::Error) -> ConnectionError {
+        match e.kind() {
+            io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => {
+                ConnectionError::NotRunning(e)
+            }
+            _ => ConnectionError::from_io(e),
+        }
+    }
+
+    /// Classify a response parse failure (an empty reply means the daemon hung up)
+    fn from_serde(e: serde_json::Error) -> ConnectionError {
+        if e.is_eof() {
+            ConnectionError::Disconnected(e.to_string())
+        } else {
+            ConnectionError::InvalidResponse(e)
+        }
+    }
+
+    /// Whether retrying the request might succeed (daemon starting, restarting or busy)
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ConnectionError::NotRunning(_)
+                | ConnectionError::Busy(_)
+                | ConnectionError::Disconnected(_)
+        )
+    }
+
+    /// Machine-readable error type used in JSON output
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            ConnectionError::NotRunning(_) => "daemon_not_running",
+            ConnectionError::Busy(_) => "daemon_busy",
+            ConnectionError::Disconnected(_) => "daemon_disconnected",
+            ConnectionError::Timeout { .. } => "timeout",
+            ConnectionError::InvalidResponse(_) => "invalid_response",
+            ConnectionError::InvalidRequest(_) => "invalid_request",
+            ConnectionError::AuthFailed(_) => "auth_failed",
+            ConnectionError::VersionMismatch { .. } => "version_mismatch",
+            ConnectionError::Io(_) => "io_error",
+            ConnectionError::RetriesExhausted { last, .. } => last.error_type(),
+        }
+    }
+
+    /// Process exit code (sysexits.h values; 124 for timeouts, like `timeout(1)`)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ConnectionError::NotRunning(_) => 69,          // EX_UNAVAILABLE
+            ConnectionError::Disconnected(_) => 70,        // EX_SOFTWARE
+            ConnectionError::Io(_) => 74,                  // EX_IOERR
+            ConnectionError::Busy(_) => 75,                // EX_TEMPFAIL
+            ConnectionError::InvalidResponse(_) => 76,     // EX_PROTOCOL
+            ConnectionError::InvalidRequest(_) => 64,      // EX_USAGE
+            ConnectionError::AuthFailed(_) => 77,          // EX_NOPERM
+            ConnectionError::VersionMismatch { .. } => 78, // EX_CONFIG
+            ConnectionError::Timeout { .. } => 124,
+            ConnectionError::RetriesExhausted { last, .. } => last.exit_code(),
+        }
+    }
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::NotRunning(e) => {
+                write!(f, "Failed to connect: {} (daemon is not running)", e)
+            }
+            ConnectionError::Busy(e) => write!(f, "Daemon is busy: {}", e),
+            ConnectionError::Disconnected(detail) => {
+                write!(f, "Daemon closed the connection: {}", detail)
+            }
+            ConnectionError::Timeout { action, ms } => write!(
+                f,
+                "Command '{}' timed out after {}ms (use --timeout <ms> to wait longer)",
+                action, ms
+            ),
+            ConnectionError::InvalidResponse(e) => write!(f, "Invalid response: {}", e),
+            ConnectionError::InvalidRequest(msg) => write!(f, "{}", msg),
+            ConnectionError::AuthFailed(msg) => write!(
+                f,
+                "Remote daemon authentication failed: {} (check AGENT_BROWSER_DAEMON_TOKEN)",
+                msg
+            ),
+            ConnectionError::VersionMismatch { daemon } => write!(
+                f,
+                "Version mismatch: CLI is {} but daemon is {} (restart the daemon from the same release)",
+                CLI_VERSION,
+                daemon.as_deref().unwrap_or("older than the version handshake")
+            ),
+            ConnectionError::Io(e) => write!(f, "Connection error: {}", e),
+            ConnectionError::RetriesExhausted { attempts, last } => write!(
+                f,
+                "{} (after {} attempts - daemon may be busy or unresponsive)",
+                last, attempts
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {}
+
+/// How often and how patiently `send_command` retries transient errors
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub retries: u32,
+    /// Delay before the first retry; retry `n` waits `n * backoff`
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            retries: 4,
+            backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+/// Connection settings derived from global flags
+#[derive(Clone, Default)]
+pub struct ConnectOptions {
+    /// Command timeout in milliseconds (`--timeout`): the wait for each reply,
+    /// and added to requests for the daemon actions that take a `timeout`
+    pub timeout: Option<u64>,
+    pub retry: RetryPolicy,
+    /// Remote daemon (`--daemon-url tcp://host:port`) used instead of the local socket
+    pub daemon_url: Option<String>,
+    /// Shared secret sent in the remote daemon handshake
+    pub daemon_token: Option<String>,
+    /// Connect even if the daemon's version differs (for status and shutdown)
+    pub skip_version_check: bool,
+}
+
+impl ConnectOptions {
+    pub fn from_flags(flags: &Flags) -> ConnectOptions {
+        let default = RetryPolicy::default();
+        ConnectOptions {
+            timeout: flags.timeout,
+            retry: RetryPolicy {
+                retries: flags.retries.unwrap_or(default.retries),
+                backoff: flags
+                    .retry_backoff
+                    .map(Duration::from_millis)
+                    .unwrap_or(default.backoff),
+            },
+            daemon_url: flags.daemon_url.clone(),
+            daemon_token: flags.daemon_token.clone(),
+            skip_version_check: false,
+        }
+    }
+}
+
+/// Versions daemons reported in this process, by daemon: `<session>:<pid>`
+/// or the remote URL. Later connections reuse them instead of repeating the
+/// hello round trip; a restarted daemon has a new pid and is asked again.
+fn known_versions() -> &'static Mutex<HashMap<String, Option<String>>> {
+    static VERSIONS: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+    VERSIONS.get_or_init(Default::default)
+}
+
+/// Which daemon a connection for `session` reaches, if it can be told
+fn daemon_key(session: &str, options: &ConnectOptions) -> Option<String> {
+    match &options.daemon_url {
+        Some(url) => Some(url.clone()),
+        None => read_pid(session).map(|pid| format!("{}:{}", session, pid)),
+    }
+}
+
+/// A request that has been sent but not yet answered
+struct InFlight {
+    action: String,
+    timeout_ms: Option<u64>,
+}
+
+/// A persistent, multiplexed connection to a session daemon.
+///
+/// Requests are written as newline-delimited JSON and may be pipelined: several
+/// requests can be sent before any reply is read. Replies are matched back to
+/// their request by the `id` field, and replies that arrive for a different
+/// request than the one being waited on are buffered until asked for.
+///
+/// The daemon may also push unsolicited `{"event": {...}}` lines (see the
+/// `events --follow` command); these are queued and read with `next_event`.
+pub struct Client {
+    reader: BufReader<Connection>,
+    writer: Connection,
+    options: ConnectOptions,
+    in_flight: HashMap<String, InFlight>,
+    pending: HashMap<String, Response>,
+    events: VecDeque<Value>,
+    /// Partially read line, kept across read timeouts
+    line: String,
+    /// Version the daemon reported in the hello handshake
+    daemon_version: Option<String>,
+}
+
+impl Client {
+    /// Open a connection to the daemon for `session`.
+    ///
+    /// With a remote `daemon_url` this opens a TCP connection instead and
+    /// authenticates with `daemon_token` before returning. Either way the
+    /// versions are then exchanged (once per daemon and process), failing
+    /// with `VersionMismatch` unless `skip_version_check` is set.
+    pub fn connect(session: &str, options: &ConnectOptions) -> Result<Client, ConnectionError> {
+        let conn = debug::timed("connect", || match &options.daemon_url {
+            Some(url) => {
+                debug::trace(|| format!("connecting to remote daemon {}", mask_proxy(url)));
+                connect_remote(url)
+            }
+            None => {
+                debug::trace(|| format!("connecting to {}", daemon_endpoint(session)));
+                connect(session)
+            }
+        })?;
+        let write_timeout = match options.timeout {
+            Some(ms) => DEFAULT_WRITE_TIMEOUT.min(Duration::from_millis(ms.max(1))),
+            None => DEFAULT_WRITE_TIMEOUT,
+        };
+        conn.set_write_timeout(Some(write_timeout)).ok();
+        let mut client = Client::from_connection(conn, options.clone())?;
+        if options.daemon_url.is_some() {
+            client.authenticate(options.daemon_token.as_deref().unwrap_or(""))?;
+        }
+        let key = daemon_key(session, options);
+        let known = key.as_ref().and_then(|key| {
+            let versions = known_versions().lock().unwrap_or_else(|e| e.into_inner());
+            versions.get(key).cloned()
+        });
+        match known {
+            Some(version) => client.daemon_version = version,
+            None => {
+                client.hello()?;
+                if let Some(key) = key {
+                    known_versions()
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(key, client.daemon_version.clone());
+                }
+            }
+        }
+        if !options.skip_version_check && client.daemon_version() != Some(CLI_VERSION) {
+            return Err(ConnectionError::VersionMismatch {
+                daemon: client.daemon_version,
+            });
+        }
+        Ok(client)
+    }
+
+    /// Exchange versions with the daemon. Daemons that predate the handshake
+    /// reject the action, which leaves the version unknown.
+    fn hello(&mut self) -> Result<(), ConnectionError> {
+        let resp = self.request(&json!({
+            "id": "hello",
+            "action": "hello",
+            "version": CLI_VERSION,
+        }))?;
+        self.daemon_version = resp
+            .data
+            .filter(|_| resp.success)
+            .and_then(|d| d.get("version")?.as_str().map(String::from));
+        Ok(())
+    }
+
+    /// Daemon version from the handshake, if it reported one
+    pub fn daemon_version(&self) -> Option<&str> {
+        self.daemon_version.as_deref()
+    }
+
+    /// Send the remote handshake (`{"token": ...}`) and wait for it to be accepted
+    fn authenticate(&mut self, token: &str) -> Result<(), ConnectionError> {
+        let mut hello = json!({ "token": token }).to_string();
+        debug::trace(|| format!("→ {}", debug::redact_line(&hello)));
+        hello.push('\n');
+        self.writer
+            .write_all(hello.as_bytes())
+            .map_err(ConnectionError::from_io)?;
+
+        self.set_read_timeout(Some(DEFAULT_WRITE_TIMEOUT))?;
+        let line = self.read_line().map_err(ConnectionError::from_io)?;
+        let resp: Response = serde_json::from_str(&line).map_err(ConnectionError::from_serde)?;
+        if resp.success {
+            Ok(())
+        } else {
+            Err(ConnectionError::AuthFailed(
+                resp.error.unwrap_or_else(|| "token rejected".to_string()),
+            ))
+        }
+    }
+
+    fn from_connection(
+        conn: Connection,
+        options: ConnectOptions,
+    ) -> Result<Client, ConnectionError> {
+        let writer = conn.try_clone().map_err(ConnectionError::Io)?;
+        Ok(Client {
+            reader: BufReader::new(conn),
+            writer,
+            options,
+            in_flight: HashMap::new(),
+            pending: HashMap::new(),
+            events: VecDeque::new(),
+            daemon_version: None,
+            line: String::new(),
+        })
+    }
+
+    /// Set how long a single read may block (`None` blocks forever).
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> Result<(), ConnectionError> {
+        self.reader
+            .get_ref()
+            .set_read_timeout(dur)
+            .map_err(ConnectionError::Io)
+    }
+
+    /// Write a request without waiting for its reply. Returns the request id.
+    ///
+    /// The `--timeout` value is added to requests for actions that take a
+    /// `timeout`, unless the request has its own; it bounds the wait for
+    /// every reply.
+    pub fn send(&mut self, cmd: &Value) -> Result<String, ConnectionError> {
+        let id = cmd
+            .get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ConnectionError::InvalidRequest("Request is missing an id".to_string()))?
+            .to_string();
+        let action = cmd
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("request")
+            .to_string();
+
+        let mut cmd = cmd.clone();
+        if let (Some(ms), Some(obj)) = (self.options.timeout, cmd.as_object_mut()) {
+            if TIMEOUT_ACTIONS.contains(&action.as_str()) {
+                obj.entry("timeout").or_insert(json!(ms));
+            }
+        }
+
+        let mut json_str = serde_json::to_string(&cmd)
+            .map_err(|e| ConnectionError::InvalidRequest(e.to_string()))?;
+        debug::trace(|| format!("→ {}", debug::redact_line(&json_str)));
+        json_str.push('\n');
+
+        self.writer
+            .write_all(json_str.as_bytes())
+            .map_err(ConnectionError::from_io)?;
+        self.in_flight.insert(
+            id.clone(),
+            InFlight {
+                action,
+                timeout_ms: cmd
+                    .get("timeout")
+                    .and_then(|v| v.as_u64())
+                    .or(self.options.timeout),
+            },
+        );
+        Ok(id)
+    }
+
+    /// Wait for the reply to the request with the given id.
+    ///
+    /// The daemon answers requests it cannot parse with an id of its own
+    /// ("unknown"/"error"); such a reply is handed to whichever request is
+    /// currently being waited on, since it cannot be correlated any further.
+    pub fn recv(&mut self, id: &str) -> Result<Response, ConnectionError> {
+        if let Some(resp) = self.pending.remove(id) {
+            self.in_flight.remove(id);
+            return Ok(resp);
+        }
+
+        let timeout = self
+            .in_flight
+            .get(id)
+            .and_then(|req| req.timeout_ms)
+            .map(|ms| Duration::from_millis(ms) + TIMEOUT_GRACE)
+            .unwrap_or(DEFAULT_READ_TIMEOUT);
+        self.set_read_timeout(Some(timeout))?;
+        let started = Instant::now();
+
+        loop {
+            let line = match self.read_line() {
+                Ok(line) => line,
+                // Only a real timeout if we actually waited that long; an early
+                // EAGAIN is reported as Busy (and retried)
+                Err(e) if is_timeout(&e) && started.elapsed() >= timeout => {
+                    let action = self
+                        .in_flight
+                        .remove(id)
+                        .map(|req| req.action)
+                        .unwrap_or_else(|| "request".to_string());
+                    return Err(ConnectionError::Timeout {
+                        action,
+                        ms: timeout.saturating_sub(TIMEOUT_GRACE).as_millis() as u64,
+                    });
+                }
+                Err(e) => return Err(ConnectionError::from_io(e)),
+            };
+            if let Some(event) = parse_event(&line) {
+                self.events.push_back(event);
+                continue;
+            }
+
+            let resp: Response =
+                serde_json::from_str(&line).map_err(ConnectionError::from_serde)?;
+
+            match resp.id.as_deref() {
+                Some(resp_id) if resp_id != id && self.in_flight.contains_key(resp_id) => {
+                    self.pending.insert(resp_id.to_string(), resp);
+                }
+                _ => {
+                    self.in_flight.remove(id);
+                    return Ok(resp);
+                }
+            }
+        }
+    }
+
+    /// Send a request and wait for its reply.
+    pub fn request(&mut self, cmd: &Value) -> Result<Response, ConnectionError> {
+        let action = cmd
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("request");
+        debug::timed(&format!("'{}' round trip", action), || {
+            let id = self.send(cmd)?;
+            self.recv(&id)
+        })
+    }
+
+    /// Wait for the next pushed event.
+    ///
+    /// Returns `Ok(None)` if the read timeout elapsed first. Replies to
+    /// in-flight requests that arrive meanwhile are buffered for `recv`.
+    pub fn next_event(&mut self) -> Result<Option<Value>, ConnectionError> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let line = match self.read_line() {
+                Ok(line) => line,
+                Err(e) if is_timeout(&e) => return Ok(None),
+                Err(e) => return Err(ConnectionError::from_io(e)),
+            };
+            if let Some(event) = parse_event(&line) {
+                return Ok(Some(event));
+            }
+
+            let resp: Response =
+                serde_json::from_str(&line).map_err(ConnectionError::from_serde)?;
+            if let Some(id) = resp.id.clone() {
+                self.pending.insert(id, resp);
+            }
+        }
+    }
+
+    /// Read one complete line. A read that times out midway keeps what it got,
+    /// so the next call resumes the same line.
+    fn read_line(&mut self) -> io::Result<String> {
+        let n = self.reader.read_line(&mut self.line)?;
+        if n == 0 && self.line.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "daemon closed the connection",
+            ));
+        }
+        debug::trace(|| format!("← {}", debug::redact_line(&self.line)));
+        Ok(std::mem::take(&mut self.line))
+    }
+}
+
+/// Whether a read failed because the socket read timeout elapsed
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Extract the payload of a pushed `{"event": {...}}` line
+fn parse_event(line: &str) -> Option<Value> {
+    if !line.starts_with("{\"event\"") {
+        return None;
+    }
+    let mut value: Value = serde_json::from_str(line).ok()?;
+    value.get_mut("event").map(Value::take)
 }
 
 /// Get the base directory for socket/pid files.
@@ -107,8 +687,95 @@ pub fn get_socket_dir() -> PathBuf {
     env::temp_dir().join("agent-browser")
 }
 
+/// Why a socket directory with this owner and mode is unsafe to use, if it is
+#[cfg(unix)]
+fn socket_dir_problem(dir: &Path, owner: u32, mode: u32, uid: u32) -> Option<String> {
+    if owner != uid {
+        return Some(format!(
+            "Socket directory '{}' is owned by uid {}, not the current user (uid {}).\n\
+             Refusing to use it; set AGENT_BROWSER_SOCKET_DIR to a directory you own.",
+            dir.display(),
+            owner,
+            uid
+        ));
+    }
+    if mode & 0o022 != 0 {
+        return Some(format!(
+            "Socket directory '{}' is writable by other users (mode {:o}).\n\
+             Refusing to use it; run 'chmod 700 {}' or set AGENT_BROWSER_SOCKET_DIR.",
+            dir.display(),
+            mode & 0o777,
+            dir.display()
+        ));
+    }
+    None
+}
+
+/// Check an existing socket directory: it must be a directory owned by the
+/// current user that nobody else can write to. One we own that others can
+/// merely read is tightened to 0700.
 #[cfg(unix)]
-fn get_socket_path(session: &str) -> PathBuf {
+fn check_socket_dir(dir: &Path) -> Result<(), String> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let meta = fs::metadata(dir)
+        .map_err(|e| format!("Cannot access socket directory '{}': {}", dir.display(), e))?;
+    if !meta.is_dir() {
+        return Err(format!(
+            "Socket directory '{}' is not a directory",
+            dir.display()
+        ));
+    }
+    let uid = unsafe { libc::geteuid() };
+    if let Some(problem) = socket_dir_problem(dir, meta.uid(), meta.mode(), uid) {
+        return Err(problem);
+    }
+    if meta.mode() & 0o077 != 0 {
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700)).map_err(|e| {
+            format!(
+                "Failed to restrict socket directory '{}': {}",
+                dir.display(),
+                e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Create the socket directory (mode 0700 on Unix) if needed and make sure it
+/// is safe to put sockets in
+pub fn prepare_socket_dir(dir: &Path) -> Result<(), String> {
+    if !dir.exists() {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder
+            .create(dir)
+            .map_err(|e| format!("Failed to create socket directory: {}", e))?;
+    }
+    #[cfg(unix)]
+    check_socket_dir(dir)?;
+    Ok(())
+}
+
+/// Options for files in the socket directory, readable only by their owner
+fn private_file_options() -> fs::OpenOptions {
+    #[allow(unused_mut)]
+    let mut options = fs::OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+#[cfg(unix)]
+pub fn get_socket_path(session: &str) -> PathBuf {
     get_socket_dir().join(format!("{}.sock", session))
 }
 
@@ -116,10 +783,152 @@ fn get_pid_path(session: &str) -> PathBuf {
     get_socket_dir().join(format!("{}.pid", session))
 }
 
+/// Daemon stdout/stderr are appended here unless `--log-file` overrides it
+pub fn get_log_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.log", session))
+}
+
+/// Log file for a session: the explicit override, else the one the running
+/// daemon was spawned with, else the default `<session>.log`
+pub fn resolve_log_path(session: &str, log_file: Option<&str>) -> PathBuf {
+    log_file
+        .map(PathBuf::from)
+        .or_else(|| {
+            read_launch_record(session)?
+                .pointer("/spawn/logFile")?
+                .as_str()
+                .map(PathBuf::from)
+        })
+        .unwrap_or_else(|| get_log_path(session))
+}
+
+/// Logs larger than this are rotated to `<log>.1` before the next daemon start
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+/// Lines of the log shown when the daemon fails to start
+const STARTUP_LOG_LINES: usize = 20;
+
+/// Move an oversized log aside, keeping a single previous generation
+fn rotate_log(path: &Path) {
+    if fs::metadata(path).is_ok_and(|m| m.len() > MAX_LOG_BYTES) {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".1");
+        let _ = fs::rename(path, backup);
+    }
+}
+
+/// Last `max_lines` lines written to the log after byte `offset`
+fn read_log_since(path: &Path, offset: u64, max_lines: usize) -> Vec<String> {
+    let Ok(mut file) = fs::File::open(path) else {
+        return Vec::new();
+    };
+    let mut text = String::new();
+    if file.seek(SeekFrom::Start(offset)).is_err() || file.read_to_string(&mut text).is_err() {
+        return Vec::new();
+    }
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(max_lines)..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// Launch options the daemon was started with (see `record_launch`)
+fn get_launch_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.launch", session))
+}
+
+/// Hash of the launch options the session's daemon was started with
+fn get_options_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.options", session))
+}
+
+/// Hash of the running daemon's launch options (see `LaunchOptions::hash`)
+pub fn read_options_hash(session: &str) -> Option<String> {
+    fs::read_to_string(get_options_path(session))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// Remember the options a daemon was spawned with, and their hash
+pub fn record_spawn(session: &str, options: &LaunchOptions) {
+    record_launch(session, "spawn", json!(options));
+    let _ = private_file_options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(get_options_path(session))
+        .and_then(|mut f| f.write_all(options.hash().as_bytes()));
+}
+
+/// Pid from the session's pid file, if any
+pub fn read_pid(session: &str) -> Option<u32> {
+    fs::read_to_string(get_pid_path(session))
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+}
+
+/// When the session's daemon started, taken from its pid file's mtime
+pub fn daemon_started(session: &str) -> Option<SystemTime> {
+    fs::metadata(get_pid_path(session)).ok()?.modified().ok()
+}
+
+/// Where the session's daemon listens: its socket path, or its local TCP port
+pub fn daemon_endpoint(session: &str) -> String {
+    #[cfg(unix)]
+    {
+        get_socket_path(session).display().to_string()
+    }
+    #[cfg(windows)]
+    {
+        format!("127.0.0.1:{}", get_port_for_session(session))
+    }
+}
+
+/// Whether a process with this pid exists
+pub fn pid_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(pid as i32, 0) == 0
+    }
+    #[cfg(windows)]
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle != 0 {
+            CloseHandle(handle);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Recorded launch options for a session: `spawn` holds the options the daemon
+/// process was started with, `launch` the last explicit launch request.
+pub fn read_launch_record(session: &str) -> Option<Value> {
+    fs::read_to_string(get_launch_path(session))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// Store one part (`spawn` or `launch`) of the session's launch record
+pub fn record_launch(session: &str, key: &str, value: Value) {
+    let mut record = read_launch_record(session).unwrap_or_else(|| json!({}));
+    record[key] = value;
+    // May hold proxy credentials
+    let _ = private_file_options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(get_launch_path(session))
+        .and_then(|mut f| f.write_all(record.to_string().as_bytes()));
+}
+
 /// Clean up stale socket and PID files for a session
-fn cleanup_stale_files(session: &str) {
+pub fn cleanup_stale_files(session: &str) {
     let pid_path = get_pid_path(session);
     let _ = fs::remove_file(&pid_path);
+    let _ = fs::remove_file(get_launch_path(session));
+    let _ = fs::remove_file(get_options_path(session));
 
     #[cfg(unix)]
     {
@@ -152,18 +961,7 @@ fn get_port_for_session(session: &str) -> u16 {
 
 #[cfg(unix)]
 fn is_daemon_running(session: &str) -> bool {
-    let pid_path = get_pid_path(session);
-    if !pid_path.exists() {
-        return false;
-    }
-    if let Ok(pid_str) = fs::read_to_string(&pid_path) {
-        if let Ok(pid) = pid_str.trim().parse::<i32>() {
-            unsafe {
-                return libc::kill(pid, 0) == 0;
-            }
-        }
-    }
-    false
+    read_pid(session).is_some_and(pid_alive)
 }
 
 #[cfg(windows)]
@@ -197,27 +995,100 @@ fn daemon_ready(session: &str) -> bool {
     }
 }
 
+/// Stdout/stderr for a spawned daemon: appended to the log, or discarded if
+/// the log can't be opened
+fn daemon_log_stdio(path: &Path) -> (Stdio, Stdio) {
+    let log = private_file_options().create(true).append(true).open(path);
+    match log.and_then(|f| Ok((f.try_clone()?, f))) {
+        Ok((out, err)) => (Stdio::from(out), Stdio::from(err)),
+        Err(_) => (Stdio::null(), Stdio::null()),
+    }
+}
+
+/// Longest wait for another process to finish starting the same session's daemon
+const STARTUP_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Take the exclusive startup lock for a session (released when the file is
+/// dropped). The lock file itself is left in place: deleting it would let a
+/// third process lock a fresh inode while the first still holds the old one.
+fn open_lock_file(path: &Path) -> Result<fs::File, String> {
+    private_file_options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open lock file '{}': {}", path.display(), e))
+}
+
+fn lock_startup(path: &Path) -> Result<fs::File, String> {
+    let file = open_lock_file(path)?;
+
+    let deadline = Instant::now() + STARTUP_LOCK_TIMEOUT;
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(file),
+            Err(fs::TryLockError::WouldBlock) if Instant::now() < deadline => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(fs::TryLockError::WouldBlock) => {
+                return Err(format!(
+                    "Timed out waiting for another agent-browser process to start the daemon (lock: {})",
+                    path.display()
+                ))
+            }
+            Err(fs::TryLockError::Error(e)) => {
+                return Err(format!("Failed to lock '{}': {}", path.display(), e))
+            }
+        }
+    }
+}
+
+/// A session's startup lock, held while its daemon is started, restarted or
+/// sent its launch options, so only one CLI does that at a time. Released
+/// when dropped.
+pub struct StartupLock {
+    _file: fs::File,
+}
+
+/// Wait for the session's startup lock
+pub fn lock_session(session: &str) -> Result<StartupLock, String> {
+    // Nobody else may plant or reach sockets (or lock files) in the directory
+    let socket_dir = get_socket_dir();
+    prepare_socket_dir(&socket_dir)?;
+    let file = debug::timed("startup lock", || {
+        lock_startup(&socket_dir.join(format!("{}.lock", session)))
+    })?;
+    Ok(StartupLock { _file: file })
+}
+
+/// The session's startup lock, or `None` if another CLI holds it right now
+pub fn try_lock_session(session: &str) -> Result<Option<StartupLock>, String> {
+    let socket_dir = get_socket_dir();
+    prepare_socket_dir(&socket_dir)?;
+    let path = socket_dir.join(format!("{}.lock", session));
+    let file = open_lock_file(&path)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(StartupLock { _file: file })),
+        Err(fs::TryLockError::WouldBlock) => Ok(None),
+        Err(fs::TryLockError::Error(e)) => {
+            Err(format!("Failed to lock '{}': {}", path.display(), e))
+        }
+    }
+}
+
 /// Result of ensure_daemon indicating whether a new daemon was started
 pub struct DaemonResult {
     /// True if we connected to an existing daemon, false if we started a new one
     pub already_running: bool,
 }
 
+/// Start the session's daemon with `options`, unless one is already running.
+/// The caller holds the session's startup lock, so a CLI waiting for it finds
+/// the daemon this one started.
 pub fn ensure_daemon(
     session: &str,
-    headed: bool,
-    executable_path: Option<&str>,
-    extensions: &[String],
-    args: Option<&str>,
-    user_agent: Option<&str>,
-    proxy: Option<&str>,
-    proxy_bypass: Option<&str>,
-    ignore_https_errors: bool,
-    allow_file_access: bool,
-    profile: Option<&str>,
-    state: Option<&str>,
-    provider: Option<&str>,
-    device: Option<&str>,
+    options: &LaunchOptions,
+    _lock: &StartupLock,
 ) -> Result<DaemonResult, String> {
     // Check if daemon is running AND responsive
     if is_daemon_running(session) && daemon_ready(session) {
@@ -226,22 +1097,25 @@ pub fn ensure_daemon(
         // (daemon has a 100ms shutdown delay, so we wait longer)
         thread::sleep(Duration::from_millis(150));
         if daemon_ready(session) {
+            debug::trace(|| {
+                format!(
+                    "daemon for '{}' already running (pid {})",
+                    session,
+                    read_pid(session).map_or("?".to_string(), |p| p.to_string())
+                )
+            });
             return Ok(DaemonResult {
                 already_running: true,
             });
         }
     }
 
+    let socket_dir = get_socket_dir();
+
     // Clean up any stale socket/pid files before starting fresh
+    debug::trace(|| format!("no daemon for '{}'; starting one", session));
     cleanup_stale_files(session);
 
-    // Ensure socket directory exists
-    let socket_dir = get_socket_dir();
-    if !socket_dir.exists() {
-        fs::create_dir_all(&socket_dir)
-            .map_err(|e| format!("Failed to create socket directory: {}", e))?;
-    }
-
     // Pre-flight check: Validate socket path length (Unix limit is 104 bytes including null terminator)
     #[cfg(unix)]
     {
@@ -296,67 +1170,48 @@ pub fn ensure_daemon(
         .find(|p| p.exists())
         .ok_or("Daemon not found. Set AGENT_BROWSER_HOME environment variable or run from project directory.")?;
 
+    let runtime = resolve_runtime(options.node_path.as_deref(), options.node_args.as_deref())?;
+
+    let log_path = options
+        .log_file
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| get_log_path(session));
+    rotate_log(&log_path);
+    // Only this start's output is shown if it fails
+    let log_offset = fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0);
+
+    debug::trace(|| {
+        let mut command = vec![runtime.program.clone()];
+        command.extend(runtime.args.iter().cloned());
+        command.push(daemon_path.display().to_string());
+        // Only the names: the values may hold credentials
+        let env: Vec<&str> = options.env_vars().iter().map(|(name, _)| *name).collect();
+        format!(
+            "spawning {} (env: {}; log: {})",
+            command.join(" "),
+            if env.is_empty() {
+                "defaults".to_string()
+            } else {
+                env.join(", ")
+            },
+            log_path.display()
+        )
+    });
+    let spawned = Instant::now();
+    let mut child: Child;
+
     // Spawn daemon as a fully detached background process
     #[cfg(unix)]
     {
         use std::os::unix::process::CommandExt;
 
-        let mut cmd = Command::new("node");
-        cmd.arg(daemon_path)
+        let mut cmd = Command::new(&runtime.program);
+        cmd.args(&runtime.args)
+            .arg(daemon_path)
             .env("AGENT_BROWSER_DAEMON", "1")
-            .env("AGENT_BROWSER_SESSION", session);
-
-        if headed {
-            cmd.env("AGENT_BROWSER_HEADED", "1");
-        }
-
-        if let Some(path) = executable_path {
-            cmd.env("AGENT_BROWSER_EXECUTABLE_PATH", path);
-        }
-
-        if !extensions.is_empty() {
-            cmd.env("AGENT_BROWSER_EXTENSIONS", extensions.join(","));
-        }
-
-        if let Some(a) = args {
-            cmd.env("AGENT_BROWSER_ARGS", a);
-        }
-
-        if let Some(ua) = user_agent {
-            cmd.env("AGENT_BROWSER_USER_AGENT", ua);
-        }
-
-        if let Some(p) = proxy {
-            cmd.env("AGENT_BROWSER_PROXY", p);
-        }
-
-        if let Some(pb) = proxy_bypass {
-            cmd.env("AGENT_BROWSER_PROXY_BYPASS", pb);
-        }
-
-        if ignore_https_errors {
-            cmd.env("AGENT_BROWSER_IGNORE_HTTPS_ERRORS", "1");
-        }
-
-        if allow_file_access {
-            cmd.env("AGENT_BROWSER_ALLOW_FILE_ACCESS", "1");
-        }
-
-        if let Some(prof) = profile {
-            cmd.env("AGENT_BROWSER_PROFILE", prof);
-        }
-
-        if let Some(st) = state {
-            cmd.env("AGENT_BROWSER_STATE", st);
-        }
-
-        if let Some(p) = provider {
-            cmd.env("AGENT_BROWSER_PROVIDER", p);
-        }
-
-        if let Some(d) = device {
-            cmd.env("AGENT_BROWSER_IOS_DEVICE", d);
-        }
+            .env("AGENT_BROWSER_SESSION", session)
+            .envs(options.env_vars());
 
         // Create new process group and session to fully detach
         unsafe {
@@ -367,9 +1222,11 @@ pub fn ensure_daemon(
             });
         }
 
-        cmd.stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
+        let (stdout, stderr) = daemon_log_stdio(&log_path);
+        child = cmd
+            .stdin(Stdio::null())
+            .stdout(stdout)
+            .stderr(stderr)
             .spawn()
             .map_err(|e| format!("Failed to start daemon: {}", e))?;
     }
@@ -378,201 +1235,179 @@ pub fn ensure_daemon(
     {
         use std::os::windows::process::CommandExt;
 
-        // On Windows, call node directly. Command::new handles PATH resolution (node.exe or node.cmd)
+        // On Windows, call the runtime directly. Command::new handles PATH resolution (node.exe or node.cmd)
         // and automatically quotes arguments containing spaces.
-        let mut cmd = Command::new("node");
-        cmd.arg(daemon_path)
+        let mut cmd = Command::new(&runtime.program);
+        cmd.args(&runtime.args)
+            .arg(daemon_path)
             .env("AGENT_BROWSER_DAEMON", "1")
-            .env("AGENT_BROWSER_SESSION", session);
-
-        if headed {
-            cmd.env("AGENT_BROWSER_HEADED", "1");
-        }
-
-        if let Some(path) = executable_path {
-            cmd.env("AGENT_BROWSER_EXECUTABLE_PATH", path);
-        }
-
-        if !extensions.is_empty() {
-            cmd.env("AGENT_BROWSER_EXTENSIONS", extensions.join(","));
-        }
-
-        if let Some(a) = args {
-            cmd.env("AGENT_BROWSER_ARGS", a);
-        }
-
-        if let Some(ua) = user_agent {
-            cmd.env("AGENT_BROWSER_USER_AGENT", ua);
-        }
-
-        if let Some(p) = proxy {
-            cmd.env("AGENT_BROWSER_PROXY", p);
-        }
-
-        if let Some(pb) = proxy_bypass {
-            cmd.env("AGENT_BROWSER_PROXY_BYPASS", pb);
-        }
-
-        if ignore_https_errors {
-            cmd.env("AGENT_BROWSER_IGNORE_HTTPS_ERRORS", "1");
-        }
-
-        if allow_file_access {
-            cmd.env("AGENT_BROWSER_ALLOW_FILE_ACCESS", "1");
-        }
-
-        if let Some(prof) = profile {
-            cmd.env("AGENT_BROWSER_PROFILE", prof);
-        }
-
-        if let Some(st) = state {
-            cmd.env("AGENT_BROWSER_STATE", st);
-        }
-
-        if let Some(p) = provider {
-            cmd.env("AGENT_BROWSER_PROVIDER", p);
-        }
-
-        if let Some(d) = device {
-            cmd.env("AGENT_BROWSER_IOS_DEVICE", d);
-        }
+            .env("AGENT_BROWSER_SESSION", session)
+            .envs(options.env_vars());
 
         // CREATE_NEW_PROCESS_GROUP | DETACHED_PROCESS
         const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
         const DETACHED_PROCESS: u32 = 0x00000008;
 
-        cmd.creation_flags(CREATE_NEW_PROCESS_GROUP | DETACHED_PROCESS)
+        let (stdout, stderr) = daemon_log_stdio(&log_path);
+
+        child = cmd
+            .creation_flags(CREATE_NEW_PROCESS_GROUP | DETACHED_PROCESS)
             .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
+            .stdout(stdout)
+            .stderr(stderr)
             .spawn()
             .map_err(|e| format!("Failed to start daemon: {}", e))?;
     }
 
     for _ in 0..50 {
         if daemon_ready(session) {
+            debug::trace(|| {
+                format!(
+                    "daemon pid {} ready after {}ms",
+                    child.id(),
+                    spawned.elapsed().as_millis()
+                )
+            });
+            record_spawn(session, options);
             return Ok(DaemonResult {
                 already_running: false,
             });
         }
+        // No point waiting out the full timeout for a daemon that already exited
+        if let Ok(Some(status)) = child.try_wait() {
+            debug::trace(|| format!("daemon exited during startup ({})", status));
+            break;
+        }
         thread::sleep(Duration::from_millis(100));
     }
 
-    Err(format!(
+    let mut message = format!(
         "Daemon failed to start (socket: {})",
         get_socket_dir().join(format!("{}.sock", session)).display()
-    ))
+    );
+    let tail = read_log_since(&log_path, log_offset, STARTUP_LOG_LINES);
+    if tail.is_empty() {
+        message.push_str(&format!("\nNo daemon output (log: {})", log_path.display()));
+    } else {
+        message.push_str(&format!("\nDaemon log ({}):", log_path.display()));
+        for line in tail {
+            message.push_str("\n  ");
+            message.push_str(&line);
+        }
+    }
+    Err(message)
 }
 
-fn connect(session: &str) -> Result<Connection, String> {
+fn connect(session: &str) -> Result<Connection, ConnectionError> {
     #[cfg(unix)]
     {
+        // Don't talk to a socket someone else could have planted
+        let socket_dir = get_socket_dir();
+        if socket_dir.exists() {
+            check_socket_dir(&socket_dir).map_err(|msg| {
+                ConnectionError::Io(io::Error::new(io::ErrorKind::PermissionDenied, msg))
+            })?;
+        }
         let socket_path = get_socket_path(session);
         UnixStream::connect(&socket_path)
             .map(Connection::Unix)
-            .map_err(|e| format!("Failed to connect: {}", e))
+            .map_err(ConnectionError::from_connect)
     }
     #[cfg(windows)]
     {
         let port = get_port_for_session(session);
         TcpStream::connect(format!("127.0.0.1:{}", port))
             .map(Connection::Tcp)
-            .map_err(|e| format!("Failed to connect: {}", e))
+            .map_err(ConnectionError::from_connect)
     }
 }
 
-pub fn send_command(cmd: Value, session: &str) -> Result<Response, String> {
-    // Retry logic for transient errors (EAGAIN/EWOULDBLOCK/connection issues)
-    const MAX_RETRIES: u32 = 5;
-    const RETRY_DELAY_MS: u64 = 200;
-
-    let mut last_error = String::new();
-
-    for attempt in 0..MAX_RETRIES {
-        if attempt > 0 {
-            thread::sleep(Duration::from_millis(RETRY_DELAY_MS * (attempt as u64)));
-        }
-
-        match send_command_once(&cmd, session) {
-            Ok(response) => return Ok(response),
-            Err(e) => {
-                if is_transient_error(&e) {
-                    last_error = e;
-                    continue;
-                }
-                // Non-transient error, fail immediately
-                return Err(e);
-            }
+/// Parse a `--daemon-url` value (`tcp://host:port`) into a socket address
+pub fn parse_daemon_url(url: &str) -> Result<String, String> {
+    let addr = url
+        .strip_prefix("tcp://")
+        .ok_or_else(|| format!("Invalid daemon URL '{}': expected tcp://host:port", url))?
+        .trim_end_matches('/');
+    match addr.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok_and(|p| p > 0) => {
+            Ok(addr.to_string())
         }
+        _ => Err(format!(
+            "Invalid daemon URL '{}': expected tcp://host:port",
+            url
+        )),
     }
-
-    Err(format!(
-        "{} (after {} retries - daemon may be busy or unresponsive)",
-        last_error, MAX_RETRIES
-    ))
 }
 
-/// Check if an error is transient and worth retrying.
-/// Transient errors include:
-/// - EAGAIN/EWOULDBLOCK (os error 35 on macOS, 11 on Linux)
-/// - EOF errors (daemon closed connection before responding)
-/// - Connection reset/broken pipe (daemon crashed or restarting)
-/// - Connection refused/socket not found (daemon still starting)
-fn is_transient_error(error: &str) -> bool {
-    error.contains("os error 35") // EAGAIN on macOS
-        || error.contains("os error 11") // EAGAIN on Linux
-        || error.contains("WouldBlock")
-        || error.contains("Resource temporarily unavailable")
-        || error.contains("EOF")
-        || error.contains("line 1 column 0") // Empty JSON response
-        || error.contains("Connection reset")
-        || error.contains("Broken pipe")
-        || error.contains("os error 54") // Connection reset by peer (macOS)
-        || error.contains("os error 104") // Connection reset by peer (Linux)
-        || error.contains("os error 2") // No such file or directory (socket gone)
-        || error.contains("os error 61") // Connection refused (macOS)
-        || error.contains("os error 111") // Connection refused (Linux)
+fn connect_remote(url: &str) -> Result<Connection, ConnectionError> {
+    let addr = parse_daemon_url(url).map_err(ConnectionError::InvalidRequest)?;
+    TcpStream::connect(addr)
+        .map(Connection::Tcp)
+        .map_err(ConnectionError::from_connect)
 }
 
-fn send_command_once(cmd: &Value, session: &str) -> Result<Response, String> {
-    let mut stream = connect(session)?;
-
-    stream.set_read_timeout(Some(Duration::from_secs(30))).ok();
-    stream.set_write_timeout(Some(Duration::from_secs(5))).ok();
-
-    let mut json_str = serde_json::to_string(cmd).map_err(|e| e.to_string())?;
-    json_str.push('\n');
-
-    stream
-        .write_all(json_str.as_bytes())
-        .map_err(|e| format!("Failed to send: {}", e))?;
-
-    let mut reader = BufReader::new(stream);
-    let mut response_line = String::new();
-    reader
-        .read_line(&mut response_line)
-        .map_err(|e| format!("Failed to read: {}", e))?;
+/// Send one request on a fresh connection, retrying transient failures
+/// (daemon starting, restarting or busy) according to `options.retry`.
+pub fn send_command(
+    cmd: Value,
+    session: &str,
+    options: &ConnectOptions,
+) -> Result<Response, ConnectionError> {
+    let policy = &options.retry;
+    let mut attempt = 0;
 
-    serde_json::from_str(&response_line).map_err(|e| format!("Invalid response: {}", e))
+    loop {
+        let err = match Client::connect(session, options).and_then(|mut c| c.request(&cmd)) {
+            Ok(response) => return Ok(response),
+            Err(e) => e,
+        };
+        if !err.is_transient() {
+            debug::trace(|| format!("attempt {} failed, not retrying: {}", attempt + 1, err));
+            return Err(err);
+        }
+        if attempt >= policy.retries {
+            debug::trace(|| format!("attempt {} failed, giving up: {}", attempt + 1, err));
+            return Err(if attempt == 0 {
+                err
+            } else {
+                ConnectionError::RetriesExhausted {
+                    attempts: attempt + 1,
+                    last: Box::new(err),
+                }
+            });
+        }
+        attempt += 1;
+        debug::trace(|| {
+            format!(
+                "attempt {} failed: {}; retrying in {}ms",
+                attempt,
+                err,
+                (policy.backoff * attempt).as_millis()
+            )
+        });
+        thread::sleep(policy.backoff * attempt);
+    }
 }
 
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use super::*;
+    use std::collections::HashSet;
     use std::sync::{Mutex, MutexGuard};
 
     // Mutex to prevent parallel tests from interfering with env vars
     static ENV_MUTEX: Mutex<()> = Mutex::new(());
 
-    /// RAII guard that locks env mutex and restores env vars on drop
-    struct EnvGuard<'a> {
+    /// RAII guard that locks env mutex and restores env vars on drop. Tests in
+    /// other modules that touch these variables use it too.
+    pub struct EnvGuard<'a> {
         _lock: MutexGuard<'a, ()>,
         vars: Vec<(String, Option<String>)>,
     }
 
     impl<'a> EnvGuard<'a> {
-        fn new(var_names: &[&str]) -> Self {
-            let lock = ENV_MUTEX.lock().unwrap();
+        pub fn new(var_names: &[&str]) -> Self {
+            let lock = ENV_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
             let vars = var_names
                 .iter()
                 .map(|&name| (name.to_string(), env::var(name).ok()))
@@ -648,102 +1483,609 @@ mod tests {
 
         let result = get_socket_dir();
         assert!(result.to_string_lossy().ends_with(".agent-browser"));
+        assert_eq!(result, dirs::home_dir().unwrap().join(".agent-browser"));
+    }
+
+    // === Client Tests ===
+
+    /// Spawn a fake daemon on one end of a socket pair that reads `expect`
+    /// request lines and then writes `replies` verbatim.
+    #[cfg(unix)]
+    fn fake_daemon(expect: usize, replies: Vec<&'static str>) -> Client {
+        let (client_end, daemon_end) = UnixStream::pair().unwrap();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(daemon_end.try_clone().unwrap());
+            let mut writer = daemon_end;
+            for _ in 0..expect {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+            }
+            for reply in replies {
+                writer.write_all(reply.as_bytes()).unwrap();
+                writer.write_all(b"\n").unwrap();
+            }
+        });
+        Client::from_connection(Connection::Unix(client_end), ConnectOptions::default()).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_connect_exchanges_versions_once() {
+        use std::os::unix::net::UnixListener;
+
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = env::temp_dir().join(format!("ab-hello-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        prepare_socket_dir(&dir).unwrap();
+        env::set_var("AGENT_BROWSER_SOCKET_DIR", &dir);
+        fs::write(dir.join("v.pid"), std::process::id().to_string()).unwrap();
+        let listener = UnixListener::bind(dir.join("v.sock")).unwrap();
+
+        // Answers hello on each connection it accepts; returns how many it saw
+        let daemon = thread::spawn(move || {
+            let mut hellos = 0;
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut writer = stream;
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let request: Value = serde_json::from_str(&line).unwrap();
+                if request["action"] == "hello" {
+                    hellos += 1;
+                    let reply = json!({ "id": "hello", "success": true, "data": { "version": CLI_VERSION } });
+                    writeln!(writer, "{}", reply).unwrap();
+                }
+            }
+            hellos
+        });
+
+        let options = ConnectOptions::default();
+        let first = Client::connect("v", &options).unwrap();
+        assert_eq!(first.daemon_version(), Some(CLI_VERSION));
+        let mut second = Client::connect("v", &options).unwrap();
+        assert_eq!(second.daemon_version(), Some(CLI_VERSION));
+        second.send(&json!({ "id": "a", "action": "url" })).unwrap();
+        assert_eq!(daemon.join().unwrap(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_request_roundtrip() {
+        let mut client = fake_daemon(1, vec![r#"{"id":"a","success":true,"data":{"ok":1}}"#]);
+        let resp = client
+            .request(&serde_json::json!({ "id": "a", "action": "url" }))
+            .unwrap();
+        assert!(resp.success);
+        assert_eq!(resp.data.unwrap()["ok"], 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_matches_out_of_order_replies() {
+        let mut client = fake_daemon(
+            2,
+            vec![
+                r#"{"id":"b","success":true,"data":{"n":"b"}}"#,
+                r#"{"id":"a","success":true,"data":{"n":"a"}}"#,
+            ],
+        );
+        client
+            .send(&serde_json::json!({ "id": "a", "action": "url" }))
+            .unwrap();
+        client
+            .send(&serde_json::json!({ "id": "b", "action": "title" }))
+            .unwrap();
+        assert_eq!(client.recv("a").unwrap().data.unwrap()["n"], "a");
+        assert_eq!(client.recv("b").unwrap().data.unwrap()["n"], "b");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_uncorrelated_error_goes_to_waiter() {
+        let mut client = fake_daemon(
+            1,
+            vec![r#"{"id":"unknown","success":false,"error":"Invalid JSON"}"#],
+        );
+        let resp = client
+            .request(&serde_json::json!({ "id": "a", "action": "bogus" }))
+            .unwrap();
+        assert!(!resp.success);
+        assert_eq!(resp.error.as_deref(), Some("Invalid JSON"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_send_requires_id() {
+        let mut client = fake_daemon(0, vec![]);
+        assert!(client
+            .send(&serde_json::json!({ "action": "url" }))
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_queues_pushed_events() {
+        let mut client = fake_daemon(
+            1,
+            vec![
+                r#"{"event":{"kind":"console","type":"log","text":"early"}}"#,
+                r#"{"id":"a","success":true,"data":{"buffered":[]}}"#,
+                r#"{"event":{"kind":"error","message":"boom"}}"#,
+            ],
+        );
+        let resp = client
+            .request(&serde_json::json!({ "id": "a", "action": "events", "follow": true }))
+            .unwrap();
+        assert!(resp.success);
+        assert_eq!(client.next_event().unwrap().unwrap()["text"], "early");
+        assert_eq!(client.next_event().unwrap().unwrap()["message"], "boom");
+        assert!(client.next_event().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_next_event_times_out() {
+        let (client_end, _daemon_end) = UnixStream::pair().unwrap();
+        let mut client =
+            Client::from_connection(Connection::Unix(client_end), ConnectOptions::default())
+                .unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_millis(20)))
+            .unwrap();
+        assert!(client.next_event().unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_forwards_timeout() {
+        let (client_end, daemon_end) = UnixStream::pair().unwrap();
+        let options = ConnectOptions {
+            timeout: Some(1500),
+            ..ConnectOptions::default()
+        };
+        let mut client = Client::from_connection(Connection::Unix(client_end), options).unwrap();
+        client
+            .send(&serde_json::json!({ "id": "a", "action": "navigate", "url": "x" }))
+            .unwrap();
+        client
+            .send(&serde_json::json!({ "id": "b", "action": "wait", "timeout": 100 }))
+            .unwrap();
+        client
+            .send(&serde_json::json!({ "id": "c", "action": "session_list" }))
+            .unwrap();
+
+        let mut reader = BufReader::new(daemon_end);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&line).unwrap()["timeout"],
+            1500
+        );
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&line).unwrap()["timeout"],
+            100
+        );
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert!(serde_json::from_str::<Value>(&line)
+            .unwrap()
+            .get("timeout")
+            .is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_timeout_names_command() {
+        let (client_end, _daemon_end) = UnixStream::pair().unwrap();
+        let options = ConnectOptions {
+            timeout: Some(50),
+            ..ConnectOptions::default()
+        };
+        let mut client = Client::from_connection(Connection::Unix(client_end), options).unwrap();
+        let err = match client.request(&serde_json::json!({ "id": "a", "action": "navigate" })) {
+            Err(e) => e,
+            Ok(_) => panic!("expected a timeout"),
+        };
         assert!(
-            result.to_string_lossy().contains("home") || result.to_string_lossy().contains("Users")
+            matches!(err, ConnectionError::Timeout { ref action, ms: 50 } if action == "navigate")
         );
+        assert!(err.to_string().contains("'navigate' timed out after 50ms"));
     }
 
-    // === Transient Error Detection Tests ===
+    #[cfg(unix)]
+    #[test]
+    fn test_client_authenticate_accepted() {
+        let mut client = fake_daemon(1, vec![r#"{"id":"auth","success":true,"data":{}}"#]);
+        assert!(client.authenticate("s3cret").is_ok());
+    }
 
+    #[cfg(unix)]
     #[test]
-    fn test_is_transient_error_eagain_macos() {
-        assert!(is_transient_error(
-            "Failed to read: Resource temporarily unavailable (os error 35)"
-        ));
+    fn test_client_authenticate_rejected() {
+        let mut client = fake_daemon(
+            1,
+            vec![r#"{"id":"auth","success":false,"error":"Invalid token"}"#],
+        );
+        let err = client.authenticate("wrong").err().unwrap();
+        assert!(matches!(err, ConnectionError::AuthFailed(ref m) if m == "Invalid token"));
+        assert_eq!(err.exit_code(), 77);
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_is_transient_error_eagain_linux() {
-        assert!(is_transient_error(
-            "Failed to read: Resource temporarily unavailable (os error 11)"
-        ));
+    fn test_client_hello_reads_daemon_version() {
+        let mut client = fake_daemon(
+            1,
+            vec![r#"{"id":"hello","success":true,"data":{"version":"9.9.9"}}"#],
+        );
+        client.hello().unwrap();
+        assert_eq!(client.daemon_version(), Some("9.9.9"));
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_is_transient_error_would_block() {
-        assert!(is_transient_error("operation WouldBlock"));
+    fn test_client_hello_old_daemon_has_no_version() {
+        let mut client = fake_daemon(
+            1,
+            vec![
+                r#"{"id":"hello","success":false,"error":"Validation error: action: Invalid discriminator value"}"#,
+            ],
+        );
+        client.hello().unwrap();
+        assert_eq!(client.daemon_version(), None);
+
+        let err = ConnectionError::VersionMismatch { daemon: None };
+        assert!(err.to_string().contains(CLI_VERSION));
+        assert!(err.to_string().contains("older than the version handshake"));
+        assert_eq!(err.exit_code(), 78);
     }
 
     #[test]
-    fn test_is_transient_error_resource_unavailable() {
-        assert!(is_transient_error("Resource temporarily unavailable"));
+    fn test_parse_daemon_url() {
+        assert_eq!(
+            parse_daemon_url("tcp://ci-browsers:9300").unwrap(),
+            "ci-browsers:9300"
+        );
+        assert_eq!(
+            parse_daemon_url("tcp://10.0.0.5:9300/").unwrap(),
+            "10.0.0.5:9300"
+        );
+        assert!(parse_daemon_url("http://host:9300").is_err());
+        assert!(parse_daemon_url("tcp://host").is_err());
+        assert!(parse_daemon_url("tcp://:9300").is_err());
+        assert!(parse_daemon_url("tcp://host:0").is_err());
     }
 
     #[test]
-    fn test_is_transient_error_eof() {
-        assert!(is_transient_error(
-            "Invalid response: EOF while parsing a value at line 1 column 0"
-        ));
+    fn test_response_id_not_serialized() {
+        let resp: Response =
+            serde_json::from_str(r#"{"id":"a","success":true,"data":null}"#).unwrap();
+        assert_eq!(resp.id.as_deref(), Some("a"));
+        let out = serde_json::to_string(&resp).unwrap();
+        assert!(!out.contains("\"id\""));
+    }
+
+    // === Connection Error Classification Tests ===
+
+    fn io_err(kind: io::ErrorKind) -> io::Error {
+        io::Error::new(kind, "test")
     }
 
     #[test]
-    fn test_is_transient_error_empty_json() {
-        assert!(is_transient_error(
-            "Invalid response: expected value at line 1 column 0"
-        ));
+    fn test_connect_missing_socket_is_not_running() {
+        let err = ConnectionError::from_connect(io_err(io::ErrorKind::NotFound));
+        assert!(matches!(err, ConnectionError::NotRunning(_)));
+        assert!(err.is_transient());
     }
 
     #[test]
-    fn test_is_transient_error_connection_reset() {
-        assert!(is_transient_error("Connection reset by peer"));
+    fn test_connect_refused_is_not_running() {
+        let err = ConnectionError::from_connect(io_err(io::ErrorKind::ConnectionRefused));
+        assert!(matches!(err, ConnectionError::NotRunning(_)));
     }
 
     #[test]
-    fn test_is_transient_error_broken_pipe() {
-        assert!(is_transient_error("Broken pipe"));
+    fn test_would_block_is_busy() {
+        let err = ConnectionError::from_connect(io_err(io::ErrorKind::WouldBlock));
+        assert!(matches!(err, ConnectionError::Busy(_)));
+        assert!(err.is_transient());
     }
 
     #[test]
-    fn test_is_transient_error_connection_reset_macos() {
-        assert!(is_transient_error(
-            "Failed to send: Connection reset by peer (os error 54)"
-        ));
+    fn test_reset_and_broken_pipe_are_disconnected() {
+        for kind in [
+            io::ErrorKind::ConnectionReset,
+            io::ErrorKind::BrokenPipe,
+            io::ErrorKind::UnexpectedEof,
+        ] {
+            let err = ConnectionError::from_io(io_err(kind));
+            assert!(
+                matches!(err, ConnectionError::Disconnected(_)),
+                "{:?}",
+                kind
+            );
+            assert!(err.is_transient());
+        }
     }
 
     #[test]
-    fn test_is_transient_error_connection_reset_linux() {
-        assert!(is_transient_error(
-            "Failed to send: Connection reset by peer (os error 104)"
-        ));
+    fn test_empty_response_is_disconnected() {
+        let serde_err = serde_json::from_str::<Value>("").unwrap_err();
+        let err = ConnectionError::from_serde(serde_err);
+        assert!(matches!(err, ConnectionError::Disconnected(_)));
     }
 
     #[test]
-    fn test_is_transient_error_socket_not_found() {
-        assert!(is_transient_error(
-            "Failed to connect: No such file or directory (os error 2)"
-        ));
+    fn test_garbage_response_is_invalid() {
+        let serde_err = serde_json::from_str::<Value>("not json").unwrap_err();
+        let err = ConnectionError::from_serde(serde_err);
+        assert!(matches!(err, ConnectionError::InvalidResponse(_)));
+        assert!(!err.is_transient());
     }
 
     #[test]
-    fn test_is_transient_error_connection_refused_macos() {
-        assert!(is_transient_error(
-            "Failed to connect: Connection refused (os error 61)"
-        ));
+    fn test_non_transient_errors() {
+        assert!(!ConnectionError::from_io(io_err(io::ErrorKind::PermissionDenied)).is_transient());
+        assert!(!ConnectionError::InvalidRequest("x".to_string()).is_transient());
+        assert!(!ConnectionError::Timeout {
+            action: "navigate".to_string(),
+            ms: 1000
+        }
+        .is_transient());
+    }
+
+    #[test]
+    fn test_exit_codes_and_types_are_distinct() {
+        let errors = [
+            ConnectionError::NotRunning(io_err(io::ErrorKind::NotFound)),
+            ConnectionError::Busy(io_err(io::ErrorKind::WouldBlock)),
+            ConnectionError::Disconnected("eof".to_string()),
+            ConnectionError::Timeout {
+                action: "wait".to_string(),
+                ms: 1,
+            },
+            ConnectionError::InvalidResponse(serde_json::from_str::<Value>("x").unwrap_err()),
+            ConnectionError::InvalidRequest("x".to_string()),
+            ConnectionError::AuthFailed("x".to_string()),
+            ConnectionError::VersionMismatch { daemon: None },
+            ConnectionError::Io(io_err(io::ErrorKind::PermissionDenied)),
+        ];
+        let codes: HashSet<i32> = errors.iter().map(|e| e.exit_code()).collect();
+        let types: HashSet<&str> = errors.iter().map(|e| e.error_type()).collect();
+        assert_eq!(codes.len(), errors.len());
+        assert_eq!(types.len(), errors.len());
+        assert!(!codes.contains(&0) && !codes.contains(&1));
+    }
+
+    #[test]
+    fn test_retries_exhausted_reports_last_error() {
+        let err = ConnectionError::RetriesExhausted {
+            attempts: 3,
+            last: Box::new(ConnectionError::Busy(io_err(io::ErrorKind::WouldBlock))),
+        };
+        assert_eq!(err.error_type(), "daemon_busy");
+        assert_eq!(err.exit_code(), 75);
+        assert!(err.to_string().contains("after 3 attempts"));
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_is_transient_error_connection_refused_linux() {
-        assert!(is_transient_error(
-            "Failed to connect: Connection refused (os error 111)"
+    fn test_send_command_follows_retry_policy() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = env::temp_dir().join(format!("ab-retry-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        env::set_var("AGENT_BROWSER_SOCKET_DIR", &dir);
+
+        let options = ConnectOptions {
+            retry: RetryPolicy {
+                retries: 2,
+                backoff: Duration::from_millis(10),
+            },
+            ..ConnectOptions::default()
+        };
+        let started = Instant::now();
+        let err = match send_command(json!({ "id": "a", "action": "url" }), "nope", &options) {
+            Err(e) => e,
+            Ok(_) => panic!("expected no daemon"),
+        };
+        // Two retries: 10ms + 20ms of backoff
+        assert!(started.elapsed() >= Duration::from_millis(30));
+        assert!(matches!(
+            err,
+            ConnectionError::RetriesExhausted { attempts: 3, .. }
         ));
+        assert_eq!(err.error_type(), "daemon_not_running");
+
+        let options = ConnectOptions {
+            retry: RetryPolicy {
+                retries: 0,
+                backoff: Duration::from_millis(10),
+            },
+            ..options
+        };
+        let err = send_command(json!({ "id": "b", "action": "url" }), "nope", &options);
+        assert!(matches!(err, Err(ConnectionError::NotRunning(_))));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rotate_log_keeps_one_generation() {
+        let dir = env::temp_dir().join(format!("ab-log-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("s.log");
+
+        fs::write(&log, "small\n").unwrap();
+        rotate_log(&log);
+        assert!(log.exists());
+
+        fs::write(&log, vec![b'x'; MAX_LOG_BYTES as usize + 1]).unwrap();
+        rotate_log(&log);
+        assert!(!log.exists());
+        assert!(dir.join("s.log.1").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_read_log_since_offset() {
+        let dir = env::temp_dir().join(format!("ab-log-tail-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("s.log");
+        fs::write(&log, "old run\n").unwrap();
+        let offset = fs::metadata(&log).unwrap().len();
+        fs::write(&log, "old run\nline 1\n\nline 2\nError: boom\n").unwrap();
+
+        assert_eq!(
+            read_log_since(&log, offset, 2),
+            vec!["line 2".to_string(), "Error: boom".to_string()]
+        );
+        assert_eq!(read_log_since(&log, offset, 10).len(), 3);
+        assert!(read_log_since(&dir.join("missing.log"), 0, 10).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_dir_problem() {
+        let dir = Path::new("/tmp/agent-browser");
+        assert!(socket_dir_problem(dir, 1000, 0o700, 1000).is_none());
+        assert!(socket_dir_problem(dir, 1000, 0o755, 1000).is_none());
+
+        let other_owner = socket_dir_problem(dir, 1234, 0o700, 1000).unwrap();
+        assert!(other_owner.contains("owned by uid 1234"));
+
+        let group_writable = socket_dir_problem(dir, 1000, 0o770, 1000).unwrap();
+        assert!(group_writable.contains("mode 770"));
+        assert!(socket_dir_problem(dir, 1000, 0o757, 1000).is_some());
+        assert!(socket_dir_problem(dir, 1000, 0o41777, 1000).is_some());
+    }
+
+    #[cfg(unix)]
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ab-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[cfg(unix)]
+    fn mode_of(path: &Path) -> u32 {
+        use std::os::unix::fs::MetadataExt;
+        fs::metadata(path).unwrap().mode() & 0o777
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_prepare_socket_dir_creates_private_dir() {
+        let dir = test_dir("sockdir-create");
+        prepare_socket_dir(&dir.join("nested")).unwrap();
+        assert_eq!(mode_of(&dir.join("nested")), 0o700);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_prepare_socket_dir_tightens_readable_dir() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = test_dir("sockdir-tighten");
+        fs::create_dir_all(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        prepare_socket_dir(&dir).unwrap();
+        assert_eq!(mode_of(&dir), 0o700);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_prepare_socket_dir_refuses_writable_dir() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = test_dir("sockdir-writable");
+        fs::create_dir_all(&dir).unwrap();
+        for mode in [0o777, 0o770] {
+            fs::set_permissions(&dir, fs::Permissions::from_mode(mode)).unwrap();
+            let err = prepare_socket_dir(&dir).unwrap_err();
+            assert!(err.contains("writable by other users"));
+            // Refused directories are left alone
+            assert_eq!(mode_of(&dir), mode);
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_prepare_socket_dir_refuses_file() {
+        let dir = test_dir("sockdir-file");
+        fs::write(&dir, "").unwrap();
+        assert!(prepare_socket_dir(&dir)
+            .unwrap_err()
+            .contains("not a directory"));
+        let _ = fs::remove_file(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_connect_refuses_insecure_socket_dir() {
+        use std::os::unix::fs::PermissionsExt;
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = test_dir("sockdir-connect");
+        fs::create_dir_all(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+        env::set_var("AGENT_BROWSER_SOCKET_DIR", &dir);
+
+        let err = match connect("planted") {
+            Err(e) => e,
+            Ok(_) => panic!("expected refusal"),
+        };
+        assert!(
+            matches!(err, ConnectionError::Io(ref e) if e.kind() == io::ErrorKind::PermissionDenied)
+        );
+        assert!(!err.is_transient());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_files_are_owner_only() {
+        let dir = test_dir("private-file");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("s.log");
+        private_file_options()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .unwrap();
+        assert_eq!(mode_of(&path), 0o600);
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn test_is_transient_error_non_transient() {
-        // These should NOT be considered transient
-        assert!(!is_transient_error("Unknown command: foo"));
-        assert!(!is_transient_error("Invalid JSON syntax"));
-        assert!(!is_transient_error("Permission denied"));
-        assert!(!is_transient_error("Daemon not found"));
+    fn test_lock_startup_is_exclusive() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let dir = env::temp_dir().join(format!("ab-lock-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("s.lock");
+
+        let first = lock_startup(&path).unwrap();
+        let released = Arc::new(AtomicBool::new(false));
+        let waiter = {
+            let path = path.clone();
+            let released = Arc::clone(&released);
+            thread::spawn(move || {
+                let _second = lock_startup(&path).unwrap();
+                // Only reachable once the first holder has let go
+                assert!(released.load(Ordering::SeqCst));
+            })
+        };
+
+        thread::sleep(Duration::from_millis(200));
+        released.store(true, Ordering::SeqCst);
+        drop(first);
+        waiter.join().unwrap();
+        assert!(path.exists());
+        let _ = fs::remove_dir_all(&dir);
     }
 }
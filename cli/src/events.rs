@@ -0,0 +1,152 @@
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+use crate::commands::parse_command;
+use crate::connection::{Client, ConnectOptions};
+use crate::flags::Flags;
+use crate::launch::prepare_daemon;
+use crate::output::{
+    format_event, print_connection_error, print_error, print_parse_error, print_response,
+};
+
+/// Value following `name` in the command arguments
+fn option_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .map(|s| s.as_str())
+}
+
+/// Whether an event's text, message or URL contains `pattern`
+fn event_matches(event: &Value, pattern: &str) -> bool {
+    ["text", "message", "url"].iter().any(|key| {
+        event
+            .get(*key)
+            .and_then(|v| v.as_str())
+            .is_some_and(|s| s.contains(pattern))
+    })
+}
+
+fn print_event(event: &Value, json_mode: bool) {
+    if json_mode {
+        println!("{}", event);
+    } else {
+        println!("{}", format_event(event));
+    }
+}
+
+/// Run `agent-browser events [kinds] [--follow] [--until <text>] [--timeout <ms>]`.
+///
+/// Without `--follow` this prints the buffered events like any other command.
+/// With it, the connection stays open and events pushed by the daemon are
+/// printed as they arrive (one JSON object per line in JSON mode) until
+/// `--until` matches a new event, `--timeout` elapses or the daemon goes away.
+/// Returns the process exit code: 1 if `--until` was given but never matched.
+pub fn run_events(args: &[String], flags: &Flags) -> i32 {
+    let cmd = match parse_command(args, flags) {
+        Ok(c) => c,
+        Err(e) => {
+            print_parse_error(&e, flags.json);
+            return 1;
+        }
+    };
+    let until = option_value(args, "--until");
+    let timeout = flags.timeout.map(Duration::from_millis);
+
+    if let Err(e) = prepare_daemon(flags) {
+        print_error(&e, flags.json);
+        return 1;
+    }
+
+    let mut client = match Client::connect(&flags.session, &ConnectOptions::from_flags(flags)) {
+        Ok(c) => c,
+        Err(e) => {
+            print_connection_error(&e, flags.json);
+            return e.exit_code();
+        }
+    };
+    let resp = match client.request(&cmd) {
+        Ok(r) => r,
+        Err(e) => {
+            print_connection_error(&e, flags.json);
+            return e.exit_code();
+        }
+    };
+
+    if cmd["follow"] != true || !resp.success {
+        print_response(&resp, flags.json, Some("events"));
+        return if resp.success { 0 } else { 1 };
+    }
+
+    let buffered = resp
+        .data
+        .as_ref()
+        .and_then(|d| d.get("buffered"))
+        .and_then(|v| v.as_array());
+    for event in buffered.into_iter().flatten() {
+        print_event(event, flags.json);
+    }
+
+    let deadline = timeout.map(|t| Instant::now() + t);
+    loop {
+        let remaining = match deadline {
+            Some(d) => match d.checked_duration_since(Instant::now()) {
+                Some(r) if !r.is_zero() => Some(r),
+                _ => break,
+            },
+            None => None,
+        };
+        if let Err(e) = client.set_read_timeout(remaining) {
+            print_connection_error(&e, flags.json);
+            return e.exit_code();
+        }
+
+        match client.next_event() {
+            Ok(Some(event)) => {
+                print_event(&event, flags.json);
+                if until.is_some_and(|p| event_matches(&event, p)) {
+                    return 0;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                print_connection_error(&e, flags.json);
+                return e.exit_code();
+            }
+        }
+    }
+
+    // Timed out: only a failure if we were waiting for something specific
+    if until.is_some() {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_option_value() {
+        let args: Vec<String> = ["events", "--until", "done", "--follow"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(option_value(&args, "--until"), Some("done"));
+        assert_eq!(option_value(&args, "--follow"), None);
+        assert_eq!(option_value(&args, "--filter"), None);
+    }
+
+    #[test]
+    fn test_event_matches_fields() {
+        let console = json!({ "kind": "console", "type": "log", "text": "ready: 42" });
+        let request =
+            json!({ "kind": "request", "method": "POST", "url": "https://x/api/checkout" });
+        assert!(event_matches(&console, "ready"));
+        assert!(event_matches(&request, "/api/checkout"));
+        assert!(!event_matches(&request, "POST"));
+    }
+}
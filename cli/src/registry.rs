@@ -0,0 +1,2046 @@
+//! The command registry: every command the CLI understands, with its
+//! arguments, flags, subcommands, help text and request builder.
+//!
+//! `parse_command`, `--help`, `help --json`, the interactive shell's
+//! completion and the shell completion scripts all read this one table, so a
+//! command is described in exactly one place.
+
+use serde::Serialize;
+
+use crate::commands::{self, Builder, EVENT_KINDS};
+use crate::completions::SHELLS;
+use crate::flags::{switch, valued, FlagSpec};
+
+/// A positional argument
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Arg {
+    pub name: &'static str,
+    pub required: bool,
+    /// Takes the rest of the arguments
+    #[serde(skip_serializing_if = "is_false")]
+    pub variadic: bool,
+    pub description: &'static str,
+    /// Values offered for completion
+    #[serde(skip_serializing_if = "is_empty")]
+    pub choices: &'static [&'static str],
+}
+
+/// A titled block of free-form help text
+#[derive(Serialize)]
+pub struct Section {
+    pub title: &'static str,
+    pub body: &'static str,
+}
+
+/// A command or subcommand
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Command {
+    pub name: &'static str,
+    #[serde(skip_serializing_if = "is_empty")]
+    pub aliases: &'static [&'static str],
+    /// Heading in the overview (top-level commands only)
+    #[serde(skip_serializing_if = "str_is_empty")]
+    pub group: &'static str,
+    pub summary: &'static str,
+    /// Shown after `agent-browser` in help and parse errors
+    pub usage: &'static str,
+    #[serde(skip_serializing_if = "str_is_empty")]
+    pub description: &'static str,
+    #[serde(skip_serializing_if = "is_empty")]
+    pub args: &'static [Arg],
+    #[serde(skip_serializing_if = "is_empty")]
+    pub flags: &'static [FlagSpec],
+    #[serde(skip_serializing_if = "is_empty")]
+    pub subcommands: &'static [Command],
+    /// Subcommand used when none is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_subcommand: Option<&'static str>,
+    /// Handled by the CLI itself (never sent to the daemon, so not
+    /// available in batch files or the shell)
+    #[serde(skip_serializing_if = "is_false")]
+    pub local: bool,
+    /// Global flags worth listing on the command's help page. These are also
+    /// accepted among the command's own arguments (in batch files and the shell)
+    #[serde(skip_serializing_if = "is_empty")]
+    pub globals: &'static [&'static str],
+    #[serde(skip_serializing_if = "is_empty")]
+    pub sections: &'static [Section],
+    #[serde(skip_serializing_if = "is_empty")]
+    pub examples: &'static [&'static str],
+    /// Turns the parsed command line into a daemon request
+    #[serde(skip)]
+    pub build: Option<Builder>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn is_empty<T>(items: &&[T]) -> bool {
+    items.is_empty()
+}
+
+fn str_is_empty(s: &&str) -> bool {
+    s.is_empty()
+}
+
+const fn arg(name: &'static str, description: &'static str) -> Arg {
+    Arg {
+        name,
+        required: true,
+        variadic: false,
+        description,
+        choices: &[],
+    }
+}
+
+const fn optional(name: &'static str, description: &'static str) -> Arg {
+    Arg {
+        required: false,
+        ..arg(name, description)
+    }
+}
+
+const fn rest(name: &'static str, description: &'static str) -> Arg {
+    Arg {
+        variadic: true,
+        ..arg(name, description)
+    }
+}
+
+const fn optional_rest(name: &'static str, description: &'static str) -> Arg {
+    Arg {
+        required: false,
+        variadic: true,
+        ..arg(name, description)
+    }
+}
+
+const fn section(title: &'static str, body: &'static str) -> Section {
+    Section { title, body }
+}
+
+/// Global flags listed on most help pages
+const COMMON: &[&str] = &["--json", "--session"];
+
+const EMPTY: Command = Command {
+    name: "",
+    aliases: &[],
+    group: "",
+    summary: "",
+    usage: "",
+    description: "",
+    args: &[],
+    flags: &[],
+    subcommands: &[],
+    default_subcommand: None,
+    local: false,
+    globals: COMMON,
+    sections: &[],
+    examples: &[],
+    build: None,
+};
+
+/// Subcommands list no global flags of their own
+const SUBCOMMAND: Command = Command {
+    globals: &[],
+    ..EMPTY
+};
+
+const SELECTOR: Arg = arg("selector", "CSS selector, XPath or @ref from snapshot");
+
+const CORE: &str = "Core Commands";
+const NAVIGATION: &str = "Navigation";
+const ELEMENTS: &str = "Elements & Input";
+const SETTINGS: &str = "Browser Settings";
+const NETWORK: &str = "Network";
+const STORAGE: &str = "Storage & State";
+const TABS: &str = "Tabs, Windows & Frames";
+const DEBUG: &str = "Debug";
+const SESSIONS: &str = "Sessions";
+const BATCH: &str = "Batch & Shell";
+const IOS: &str = "iOS";
+const SETUP: &str = "Setup";
+
+/// Actions `find` can perform on what it finds
+const FIND_ARGS: &[Arg] = &[
+    arg("value", "What to look for"),
+    optional(
+        "action",
+        "click, fill, type, hover, focus, check or uncheck (default: click)",
+    ),
+    optional_rest("text", "Text for fill and type"),
+];
+
+/// Every command, in overview order
+pub const REGISTRY: &[Command] = &[
+    // === Core ===
+    Command {
+        name: "open",
+        aliases: &["goto", "navigate"],
+        group: CORE,
+        summary: "Navigate to a URL",
+        usage: "open <url>",
+        description: "Navigates the browser to the specified URL. If no protocol is provided,\n\
+                      https:// is automatically prepended.",
+        args: &[arg("url", "Page to open")],
+        globals: &["--json", "--session", "--headers", "--headed"],
+        sections: &[section(
+            "Headers",
+            "Headers given with --headers are only sent to the opened URL's origin,\n\
+             not to other domains.",
+        )],
+        examples: &[
+            "agent-browser open example.com",
+            "agent-browser open https://github.com",
+            "agent-browser open localhost:3000",
+            r#"agent-browser open api.example.com --headers '{"Authorization": "Bearer token"}'"#,
+        ],
+        build: Some(commands::parse_open),
+        ..EMPTY
+    },
+    Command {
+        name: "click",
+        group: CORE,
+        summary: "Click an element",
+        usage: "click <selector>",
+        description: "Clicks on the specified element. The selector can be a CSS selector,\n\
+                      XPath, or an element reference from snapshot (e.g., @e1).",
+        args: &[SELECTOR],
+        examples: &[
+            r##"agent-browser click "#submit-button""##,
+            "agent-browser click @e1",
+            r#"agent-browser click "button.primary""#,
+            r#"agent-browser click "//button[@type='submit']""#,
+        ],
+        build: Some(commands::parse_selector_action),
+        ..EMPTY
+    },
+    Command {
+        name: "dblclick",
+        group: CORE,
+        summary: "Double-click an element",
+        usage: "dblclick <selector>",
+        description: "Double-clicks on the specified element. Useful for text selection\n\
+                      or triggering double-click handlers.",
+        args: &[SELECTOR],
+        examples: &[
+            r##"agent-browser dblclick "#editable-text""##,
+            "agent-browser dblclick @e5",
+        ],
+        build: Some(commands::parse_selector_action),
+        ..EMPTY
+    },
+    Command {
+        name: "type",
+        group: CORE,
+        summary: "Type text into an element",
+        usage: "type <selector> <text>",
+        description: "Types text into the specified element character by character.\n\
+                      Unlike fill, this does not clear existing content first.",
+        args: &[SELECTOR, rest("text", "Text to type")],
+        examples: &[
+            r##"agent-browser type "#search" "hello""##,
+            r#"agent-browser type @e2 "additional text""#,
+        ],
+        build: Some(commands::parse_type),
+        ..EMPTY
+    },
+    Command {
+        name: "fill",
+        group: CORE,
+        summary: "Clear and fill an input field",
+        usage: "fill <selector> <text>",
+        description: "Clears the input field and fills it with the specified text.\n\
+                      This replaces any existing content in the field.",
+        args: &[SELECTOR, rest("text", "Text to fill in")],
+        examples: &[
+            r##"agent-browser fill "#email" "user@example.com""##,
+            r#"agent-browser fill @e3 "Hello World""#,
+            r#"agent-browser fill "input[name='search']" "query""#,
+        ],
+        build: Some(commands::parse_fill),
+        ..EMPTY
+    },
+    Command {
+        name: "press",
+        aliases: &["key"],
+        group: CORE,
+        summary: "Press a key or key combination (Enter, Tab, Control+a)",
+        usage: "press <key>",
+        description: "Presses a key or key combination. Supports special keys and modifiers.",
+        args: &[arg("key", "Key name, optionally with modifiers (Control+a)")],
+        sections: &[
+            section(
+                "Special Keys",
+                "Enter, Tab, Escape, Backspace, Delete, Space\n\
+                 ArrowUp, ArrowDown, ArrowLeft, ArrowRight\n\
+                 Home, End, PageUp, PageDown\n\
+                 F1-F12",
+            ),
+            section("Modifiers (combine with +)", "Control, Alt, Shift, Meta"),
+        ],
+        examples: &[
+            "agent-browser press Enter",
+            "agent-browser press Tab",
+            "agent-browser press Control+a",
+            "agent-browser press Control+Shift+s",
+            "agent-browser press Escape",
+        ],
+        build: Some(commands::parse_key_action),
+        ..EMPTY
+    },
+    Command {
+        name: "keydown",
+        group: CORE,
+        summary: "Press a key down (without release)",
+        usage: "keydown <key>",
+        description: "Presses a key down without releasing it. Use keyup to release.\n\
+                      Useful for holding modifier keys.",
+        args: &[arg("key", "Key to hold down")],
+        examples: &[
+            "agent-browser keydown Shift",
+            "agent-browser keydown Control",
+        ],
+        build: Some(commands::parse_key_action),
+        ..EMPTY
+    },
+    Command {
+        name: "keyup",
+        group: CORE,
+        summary: "Release a key",
+        usage: "keyup <key>",
+        description: "Releases a key that was pressed with keydown.",
+        args: &[arg("key", "Key to release")],
+        examples: &["agent-browser keyup Shift", "agent-browser keyup Control"],
+        build: Some(commands::parse_key_action),
+        ..EMPTY
+    },
+    Command {
+        name: "hover",
+        group: CORE,
+        summary: "Hover over an element",
+        usage: "hover <selector>",
+        description: "Moves the mouse to hover over the specified element. Useful for\n\
+                      triggering hover states or dropdown menus.",
+        args: &[SELECTOR],
+        examples: &[
+            r##"agent-browser hover "#dropdown-trigger""##,
+            "agent-browser hover @e4",
+        ],
+        build: Some(commands::parse_selector_action),
+        ..EMPTY
+    },
+    Command {
+        name: "focus",
+        group: CORE,
+        summary: "Focus an element",
+        usage: "focus <selector>",
+        description: "Sets keyboard focus to the specified element.",
+        args: &[SELECTOR],
+        examples: &[
+            r##"agent-browser focus "#input-field""##,
+            "agent-browser focus @e2",
+        ],
+        build: Some(commands::parse_selector_action),
+        ..EMPTY
+    },
+    Command {
+        name: "check",
+        group: CORE,
+        summary: "Check a checkbox",
+        usage: "check <selector>",
+        description: "Checks a checkbox element. If already checked, no action is taken.",
+        args: &[SELECTOR],
+        examples: &[
+            r##"agent-browser check "#terms-checkbox""##,
+            "agent-browser check @e7",
+        ],
+        build: Some(commands::parse_selector_action),
+        ..EMPTY
+    },
+    Command {
+        name: "uncheck",
+        group: CORE,
+        summary: "Uncheck a checkbox",
+        usage: "uncheck <selector>",
+        description: "Unchecks a checkbox element. If already unchecked, no action is taken.",
+        args: &[SELECTOR],
+        examples: &[
+            r##"agent-browser uncheck "#newsletter-opt-in""##,
+            "agent-browser uncheck @e8",
+        ],
+        build: Some(commands::parse_selector_action),
+        ..EMPTY
+    },
+    Command {
+        name: "select",
+        group: CORE,
+        summary: "Select dropdown options",
+        usage: "select <selector> <value...>",
+        description: "Selects one or more options in a <select> dropdown by value.",
+        args: &[SELECTOR, rest("value", "Option values to select")],
+        examples: &[
+            r##"agent-browser select "#country" "US""##,
+            r#"agent-browser select @e5 "option2""#,
+            r##"agent-browser select "#menu" "opt1" "opt2" "opt3""##,
+        ],
+        build: Some(commands::parse_select),
+        ..EMPTY
+    },
+    Command {
+        name: "drag",
+        group: CORE,
+        summary: "Drag and drop",
+        usage: "drag <source> <target>",
+        description: "Drags an element from source to target location.",
+        args: &[
+            arg("source", "Element to drag"),
+            arg("target", "Element to drop it on"),
+        ],
+        examples: &[
+            r##"agent-browser drag "#draggable" "#drop-zone""##,
+            "agent-browser drag @e1 @e2",
+        ],
+        build: Some(commands::parse_drag),
+        ..EMPTY
+    },
+    Command {
+        name: "upload",
+        group: CORE,
+        summary: "Upload files",
+        usage: "upload <selector> <files...>",
+        description: "Uploads one or more files to a file input element.",
+        args: &[SELECTOR, rest("files", "Files to upload")],
+        examples: &[
+            r##"agent-browser upload "#file-input" ./document.pdf"##,
+            "agent-browser upload @e3 ./image1.png ./image2.png",
+        ],
+        build: Some(commands::parse_upload),
+        ..EMPTY
+    },
+    Command {
+        name: "download",
+        group: CORE,
+        summary: "Download a file by clicking an element",
+        usage: "download <selector> <path>",
+        description: "Clicks an element that triggers a download and saves the file to the specified path.",
+        args: &[
+            arg("selector", "Element to click (CSS selector or @ref)"),
+            arg("path", "Path where the downloaded file will be saved"),
+        ],
+        examples: &[
+            r##"agent-browser download "#download-btn" ./file.pdf"##,
+            "agent-browser download @e5 ./report.xlsx",
+            r#"agent-browser download "a[href$='.zip']" ./archive.zip"#,
+        ],
+        build: Some(commands::parse_download),
+        ..EMPTY
+    },
+    Command {
+        name: "scroll",
+        group: CORE,
+        summary: "Scroll the page (up/down/left/right)",
+        usage: "scroll [direction] [amount]",
+        description: "Scrolls the page in the specified direction.",
+        args: &[
+            Arg {
+                choices: &["up", "down", "left", "right"],
+                ..optional("direction", "up, down, left, right (default: down)")
+            },
+            optional("amount", "Pixels to scroll (default: 300)"),
+        ],
+        examples: &[
+            "agent-browser scroll",
+            "agent-browser scroll down 500",
+            "agent-browser scroll up 200",
+            "agent-browser scroll left 100",
+        ],
+        build: Some(commands::parse_scroll),
+        ..EMPTY
+    },
+    Command {
+        name: "scrollintoview",
+        aliases: &["scrollinto"],
+        group: CORE,
+        summary: "Scroll an element into view",
+        usage: "scrollintoview <selector>",
+        description: "Scrolls the page until the specified element is visible in the viewport.",
+        args: &[SELECTOR],
+        examples: &[
+            r##"agent-browser scrollintoview "#footer""##,
+            "agent-browser scrollintoview @e15",
+        ],
+        build: Some(commands::parse_selector_action),
+        ..EMPTY
+    },
+    Command {
+        name: "wait",
+        group: CORE,
+        summary: "Wait for an element, a time or a condition",
+        usage: "wait <selector|ms|--url|--load|--fn|--text|--download>",
+        description: "Waits for an element to appear, a number of milliseconds, or one of the\n\
+                      conditions below.",
+        args: &[optional(
+            "selector|ms",
+            "Element to wait for, or milliseconds to sleep",
+        )],
+        flags: &[
+            valued(&["--url", "-u"], "<pattern>", "Wait for URL to match pattern"),
+            valued(
+                &["--load", "-l"],
+                "<state>",
+                "Wait for load state (load, domcontentloaded, networkidle)",
+            ),
+            valued(
+                &["--fn", "-f"],
+                "<expression>",
+                "Wait for JavaScript expression to be truthy",
+            ),
+            valued(&["--text", "-t"], "<text>", "Wait for text to appear on page"),
+            // The optional path is an ordinary argument
+            switch(
+                &["--download", "-d"],
+                "Wait for a download to complete (save it to the path argument, if given)",
+            ),
+        ],
+        globals: &["--timeout", "--json", "--session"],
+        examples: &[
+            r##"agent-browser wait "#loading-spinner""##,
+            "agent-browser wait 2000",
+            r#"agent-browser wait --url "**/dashboard""#,
+            "agent-browser wait --load networkidle",
+            r#"agent-browser wait --fn "window.appReady === true""#,
+            r#"agent-browser wait --text "Welcome back""#,
+            "agent-browser wait --download ./file.pdf",
+            "agent-browser wait --download ./report.xlsx --timeout 30000",
+        ],
+        build: Some(commands::parse_wait),
+        ..EMPTY
+    },
+    Command {
+        name: "screenshot",
+        group: CORE,
+        summary: "Take a screenshot",
+        usage: "screenshot [selector] [path]",
+        description: "Captures a screenshot of the current page, or of one element. If no path\n\
+                      is provided, saves to a temporary directory with a generated filename.",
+        args: &[
+            optional("selector", "Element to capture (@ref, .class or #id)"),
+            optional("path", "File to save to"),
+        ],
+        globals: &["--full", "--json", "--session"],
+        examples: &[
+            "agent-browser screenshot",
+            "agent-browser screenshot ./screenshot.png",
+            "agent-browser screenshot --full ./full-page.png",
+            "agent-browser screenshot @e3 ./button.png",
+        ],
+        build: Some(commands::parse_screenshot),
+        ..EMPTY
+    },
+    Command {
+        name: "pdf",
+        group: CORE,
+        summary: "Save the page as PDF",
+        usage: "pdf <path>",
+        description: "Saves the current page as a PDF file.",
+        args: &[arg("path", "File to save to")],
+        examples: &[
+            "agent-browser pdf ./page.pdf",
+            "agent-browser pdf ~/Documents/report.pdf",
+        ],
+        build: Some(commands::parse_pdf),
+        ..EMPTY
+    },
+    Command {
+        name: "snapshot",
+        group: CORE,
+        summary: "Accessibility tree with refs (for AI)",
+        usage: "snapshot [options]",
+        description: "Returns an accessibility tree representation of the page with element\n\
+                      references (like @e1, @e2) that can be used in subsequent commands.\n\
+                      Designed for AI agents to understand page structure.",
+        flags: &[
+            switch(&["--interactive", "-i"], "Only include interactive elements"),
+            switch(&["--compact", "-c"], "Remove empty structural elements"),
+            switch(
+                &["--cursor", "-C"],
+                "Include cursor-interactive elements (cursor:pointer, onclick, tabindex)",
+            ),
+            valued(&["--depth", "-d"], "<n>", "Limit tree depth"),
+            valued(&["--selector", "-s"], "<sel>", "Scope snapshot to CSS selector"),
+        ],
+        examples: &[
+            "agent-browser snapshot",
+            "agent-browser snapshot -i",
+            "agent-browser snapshot -i -C",
+            "agent-browser snapshot --compact --depth 5",
+            r##"agent-browser snapshot -s "#main-content""##,
+        ],
+        build: Some(commands::parse_snapshot),
+        ..EMPTY
+    },
+    Command {
+        name: "eval",
+        group: CORE,
+        summary: "Run JavaScript",
+        usage: "eval [options] <script>",
+        description: "Executes JavaScript code in the browser context and returns the result.",
+        args: &[optional_rest("script", "JavaScript to run (omit with --stdin)")],
+        sections: &[section(
+            "Multi-line scripts",
+            "Pass them on stdin, for example with a heredoc:\n\
+             \n\
+             cat <<'EOF' | agent-browser eval --stdin\n\
+             const links = document.querySelectorAll('a');\n\
+             links.length;\n\
+             EOF",
+        )],
+        flags: &[
+            switch(
+                &["--base64", "-b"],
+                "Decode script from base64 (avoids shell escaping issues)",
+            ),
+            switch(
+                &["--stdin"],
+                "Read script from stdin (useful for heredocs/multiline)",
+            ),
+        ],
+        examples: &[
+            r#"agent-browser eval "document.title""#,
+            r#"agent-browser eval "window.location.href""#,
+            r#"agent-browser eval "document.querySelectorAll('a').length""#,
+            r#"agent-browser eval -b "ZG9jdW1lbnQudGl0bGU=""#,
+        ],
+        build: Some(commands::parse_eval),
+        ..EMPTY
+    },
+    Command {
+        name: "connect",
+        group: CORE,
+        summary: "Connect to a browser via CDP",
+        usage: "connect <port|url>",
+        description: "Connects to a running browser instance via Chrome DevTools Protocol (CDP).\n\
+                      This allows controlling browsers, Electron apps, or remote browser services.",
+        args: &[arg(
+            "port|url",
+            "Local port number (e.g., 9222) or full WebSocket URL (ws://, wss://, http://, https://)",
+        )],
+        sections: &[
+            section(
+                "Supported URL formats",
+                "- Port number: 9222 (connects to http://localhost:9222)\n\
+                 - WebSocket URL: ws://localhost:9222/devtools/browser/...\n\
+                 - Remote service: wss://remote-browser.example.com/cdp?token=...",
+            ),
+            section(
+                "Local Chrome",
+                "Start Chrome with remote debugging (google-chrome --remote-debugging-port=9222),\n\
+                 then connect to the port or to the WebSocket URL from its /json/version\n\
+                 endpoint. After connecting, run commands normally.",
+            ),
+        ],
+        examples: &[
+            "agent-browser connect 9222",
+            r#"agent-browser connect "ws://localhost:9222/devtools/browser/abc123""#,
+            r#"agent-browser connect "wss://browser-service.example.com/cdp?token=xyz""#,
+        ],
+        build: Some(commands::parse_connect),
+        ..EMPTY
+    },
+    Command {
+        name: "close",
+        aliases: &["quit", "exit"],
+        group: CORE,
+        summary: "Close the browser",
+        usage: "close",
+        description: "Closes the browser instance for the current session.",
+        examples: &[
+            "agent-browser close",
+            "agent-browser close --session mysession",
+        ],
+        build: Some(commands::parse_simple),
+        ..EMPTY
+    },
+    // === Navigation ===
+    Command {
+        name: "back",
+        group: NAVIGATION,
+        summary: "Go back in history",
+        usage: "back",
+        description: "Goes back one page in the browser history, equivalent to clicking\n\
+                      the browser's back button.",
+        examples: &["agent-browser back"],
+        build: Some(commands::parse_simple),
+        ..EMPTY
+    },
+    Command {
+        name: "forward",
+        group: NAVIGATION,
+        summary: "Go forward in history",
+        usage: "forward",
+        description: "Goes forward one page in the browser history, equivalent to clicking\n\
+                      the browser's forward button.",
+        examples: &["agent-browser forward"],
+        build: Some(commands::parse_simple),
+        ..EMPTY
+    },
+    Command {
+        name: "reload",
+        group: NAVIGATION,
+        summary: "Reload the current page",
+        usage: "reload",
+        description: "Reloads the current page, equivalent to pressing F5 or clicking\n\
+                      the browser's reload button.",
+        examples: &["agent-browser reload"],
+        build: Some(commands::parse_simple),
+        ..EMPTY
+    },
+    // === Elements & input ===
+    Command {
+        name: "get",
+        group: ELEMENTS,
+        summary: "Retrieve information from elements or the page",
+        usage: "get <text|html|value|attr|url|title|count|box|styles> [args...]",
+        description: "Retrieves various types of information from elements or the page.",
+        subcommands: &[
+            Command {
+                name: "text",
+                summary: "Get text content of element",
+                usage: "get text <selector>",
+                args: &[SELECTOR],
+                build: Some(commands::parse_get),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "html",
+                summary: "Get inner HTML of element",
+                usage: "get html <selector>",
+                args: &[SELECTOR],
+                build: Some(commands::parse_get),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "value",
+                summary: "Get value of input element",
+                usage: "get value <selector>",
+                args: &[SELECTOR],
+                build: Some(commands::parse_get),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "attr",
+                summary: "Get attribute value",
+                usage: "get attr <selector> <attribute>",
+                args: &[SELECTOR, arg("attribute", "Attribute name")],
+                build: Some(commands::parse_get),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "url",
+                summary: "Get current URL",
+                usage: "get url",
+                build: Some(commands::parse_get),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "title",
+                summary: "Get page title",
+                usage: "get title",
+                build: Some(commands::parse_get),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "count",
+                summary: "Count matching elements",
+                usage: "get count <selector>",
+                args: &[SELECTOR],
+                build: Some(commands::parse_get),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "box",
+                summary: "Get bounding box (x, y, width, height)",
+                usage: "get box <selector>",
+                args: &[SELECTOR],
+                build: Some(commands::parse_get),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "styles",
+                summary: "Get computed styles of elements",
+                usage: "get styles <selector>",
+                args: &[SELECTOR],
+                build: Some(commands::parse_get),
+                ..SUBCOMMAND
+            },
+        ],
+        examples: &[
+            "agent-browser get text @e1",
+            r##"agent-browser get html "#content""##,
+            r##"agent-browser get value "#email-input""##,
+            r##"agent-browser get attr "#link" href"##,
+            "agent-browser get title",
+            "agent-browser get url",
+            r#"agent-browser get count "li.item""#,
+            r##"agent-browser get box "#header""##,
+            r#"agent-browser get styles "button""#,
+            "agent-browser get styles @e1",
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "is",
+        group: ELEMENTS,
+        summary: "Check element state",
+        usage: "is <visible|enabled|checked> <selector>",
+        description: "Checks the state of an element and returns true/false.",
+        subcommands: &[
+            Command {
+                name: "visible",
+                summary: "Check if element is visible",
+                usage: "is visible <selector>",
+                args: &[SELECTOR],
+                build: Some(commands::parse_is),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "enabled",
+                summary: "Check if element is enabled (not disabled)",
+                usage: "is enabled <selector>",
+                args: &[SELECTOR],
+                build: Some(commands::parse_is),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "checked",
+                summary: "Check if checkbox/radio is checked",
+                usage: "is checked <selector>",
+                args: &[SELECTOR],
+                build: Some(commands::parse_is),
+                ..SUBCOMMAND
+            },
+        ],
+        examples: &[
+            r##"agent-browser is visible "#modal""##,
+            r##"agent-browser is enabled "#submit-btn""##,
+            r##"agent-browser is checked "#agree-checkbox""##,
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "find",
+        group: ELEMENTS,
+        summary: "Find and interact with elements by locator",
+        usage: "find <locator> <value> [action] [text]",
+        description: "Finds elements using semantic locators and optionally performs an action\n\
+                      (default: click).",
+        flags: &[
+            valued(&["--name"], "<name>", "Filter role by accessible name"),
+            switch(&["--exact"], "Require exact text match"),
+        ],
+        subcommands: &[
+            Command {
+                name: "role",
+                summary: "Find by ARIA role (--name <n>, --exact)",
+                usage: "find role <role> [action] [text] [--name <name>] [--exact]",
+                args: FIND_ARGS,
+                build: Some(commands::parse_find),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "text",
+                summary: "Find by text content (--exact)",
+                usage: "find text <text> [action] [--exact]",
+                args: FIND_ARGS,
+                build: Some(commands::parse_find),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "label",
+                summary: "Find by associated label (--exact)",
+                usage: "find label <label> [action] [text] [--exact]",
+                args: FIND_ARGS,
+                build: Some(commands::parse_find),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "placeholder",
+                summary: "Find by placeholder text (--exact)",
+                usage: "find placeholder <text> [action] [text] [--exact]",
+                args: FIND_ARGS,
+                build: Some(commands::parse_find),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "alt",
+                summary: "Find by alt text (--exact)",
+                usage: "find alt <text> [action] [--exact]",
+                args: FIND_ARGS,
+                build: Some(commands::parse_find),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "title",
+                summary: "Find by title attribute (--exact)",
+                usage: "find title <text> [action] [--exact]",
+                args: FIND_ARGS,
+                build: Some(commands::parse_find),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "testid",
+                summary: "Find by data-testid attribute",
+                usage: "find testid <id> [action] [text]",
+                args: FIND_ARGS,
+                build: Some(commands::parse_find),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "first",
+                summary: "First matching element",
+                usage: "find first <selector> [action] [text]",
+                args: FIND_ARGS,
+                build: Some(commands::parse_find),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "last",
+                summary: "Last matching element",
+                usage: "find last <selector> [action] [text]",
+                args: FIND_ARGS,
+                build: Some(commands::parse_find),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "nth",
+                summary: "Nth matching element (0-based)",
+                usage: "find nth <index> <selector> [action] [text]",
+                args: &[
+                    arg("index", "Position among the matches, from 0 (-1 is the last)"),
+                    SELECTOR,
+                    optional("action", "Action to perform (default: click)"),
+                    optional_rest("text", "Text for fill and type"),
+                ],
+                build: Some(commands::parse_find),
+                ..SUBCOMMAND
+            },
+        ],
+        sections: &[section(
+            "Actions (default: click)",
+            "click, fill, type, hover, focus, check, uncheck",
+        )],
+        examples: &[
+            "agent-browser find role button click --name Submit",
+            r#"agent-browser find text "Sign In" click"#,
+            r#"agent-browser find label "Email" fill "user@example.com""#,
+            r#"agent-browser find placeholder "Search..." type "query""#,
+            r#"agent-browser find testid "login-form" click"#,
+            r#"agent-browser find first "li.item" click"#,
+            r#"agent-browser find nth 2 ".card" hover"#,
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "mouse",
+        group: ELEMENTS,
+        summary: "Low-level mouse operations",
+        usage: "mouse <move|down|up|wheel> [args...]",
+        description: "Performs low-level mouse operations for precise control.",
+        subcommands: &[
+            Command {
+                name: "move",
+                summary: "Move mouse to coordinates",
+                usage: "mouse move <x> <y>",
+                args: &[arg("x", "Horizontal position"), arg("y", "Vertical position")],
+                build: Some(commands::parse_mouse),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "down",
+                summary: "Press mouse button (left, right, middle)",
+                usage: "mouse down [button]",
+                args: &[optional("button", "left, right or middle (default: left)")],
+                build: Some(commands::parse_mouse),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "up",
+                summary: "Release mouse button",
+                usage: "mouse up [button]",
+                args: &[optional("button", "left, right or middle (default: left)")],
+                build: Some(commands::parse_mouse),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "wheel",
+                summary: "Scroll mouse wheel",
+                usage: "mouse wheel [dy] [dx]",
+                args: &[
+                    optional("dy", "Vertical delta (default: 100)"),
+                    optional("dx", "Horizontal delta (default: 0)"),
+                ],
+                build: Some(commands::parse_mouse),
+                ..SUBCOMMAND
+            },
+        ],
+        examples: &[
+            "agent-browser mouse move 100 200",
+            "agent-browser mouse down",
+            "agent-browser mouse up",
+            "agent-browser mouse down right",
+            "agent-browser mouse wheel 100",
+            "agent-browser mouse wheel -50 0",
+        ],
+        ..EMPTY
+    },
+    // === Browser settings ===
+    Command {
+        name: "set",
+        group: SETTINGS,
+        summary: "Configure browser settings",
+        usage: "set <viewport|device|geo|offline|headers|credentials|media> [args...]",
+        description: "Configures various browser settings and emulation options.",
+        subcommands: &[
+            Command {
+                name: "viewport",
+                summary: "Set viewport size",
+                usage: "set viewport <width> <height>",
+                args: &[arg("width", "Pixels"), arg("height", "Pixels")],
+                build: Some(commands::parse_set),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "device",
+                summary: r#"Emulate device (e.g., "iPhone 12")"#,
+                usage: "set device <name>",
+                args: &[arg("name", "Device name")],
+                build: Some(commands::parse_set),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "geo",
+                aliases: &["geolocation"],
+                summary: "Set geolocation",
+                usage: "set geo <latitude> <longitude>",
+                args: &[arg("latitude", "Degrees"), arg("longitude", "Degrees")],
+                build: Some(commands::parse_set),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "offline",
+                summary: "Toggle offline mode",
+                usage: "set offline [on|off]",
+                args: &[Arg {
+                    choices: &["on", "off"],
+                    ..optional("on|off", "Default: on")
+                }],
+                build: Some(commands::parse_set),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "headers",
+                summary: "Set extra HTTP headers",
+                usage: "set headers <json>",
+                args: &[arg("json", "Header names and values as a JSON object")],
+                build: Some(commands::parse_set),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "credentials",
+                aliases: &["auth"],
+                summary: "Set HTTP authentication",
+                usage: "set credentials <username> <password>",
+                args: &[arg("username", "User name"), arg("password", "Password")],
+                build: Some(commands::parse_set),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "media",
+                summary: "Set color scheme and reduced motion preferences",
+                usage: "set media [dark|light] [reduced-motion]",
+                args: &[
+                    Arg {
+                        choices: &["dark", "light"],
+                        ..optional("dark|light", "Color scheme (default: no preference)")
+                    },
+                    Arg {
+                        choices: &["reduced-motion"],
+                        ..optional("reduced-motion", "Enable reduced motion")
+                    },
+                ],
+                build: Some(commands::parse_set),
+                ..SUBCOMMAND
+            },
+        ],
+        examples: &[
+            "agent-browser set viewport 1920 1080",
+            r#"agent-browser set device "iPhone 12""#,
+            "agent-browser set geo 37.7749 -122.4194",
+            "agent-browser set offline on",
+            r#"agent-browser set headers '{"X-Custom": "value"}'"#,
+            "agent-browser set credentials admin secret123",
+            "agent-browser set media dark",
+            "agent-browser set media light reduced-motion",
+        ],
+        ..EMPTY
+    },
+    // === Network ===
+    Command {
+        name: "network",
+        group: NETWORK,
+        summary: "Network interception and monitoring",
+        usage: "network <route|unroute|requests> [args...]",
+        description: "Intercept, mock, or monitor network requests.",
+        subcommands: &[
+            Command {
+                name: "route",
+                summary: "Intercept requests matching URL pattern",
+                usage: "network route <url> [--abort|--body <json>]",
+                args: &[arg("url", "URL glob pattern")],
+                flags: &[
+                    switch(&["--abort"], "Abort matching requests"),
+                    valued(&["--body"], "<json>", "Respond with custom body"),
+                ],
+                build: Some(commands::parse_network),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "unroute",
+                summary: "Remove route (all if no URL)",
+                usage: "network unroute [url]",
+                args: &[optional("url", "Pattern given to route")],
+                build: Some(commands::parse_network),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "requests",
+                summary: "List captured requests",
+                usage: "network requests [--clear] [--filter <pattern>]",
+                flags: &[
+                    switch(&["--clear"], "Clear request log"),
+                    valued(&["--filter"], "<pattern>", "Filter by URL pattern"),
+                ],
+                build: Some(commands::parse_network),
+                ..SUBCOMMAND
+            },
+        ],
+        examples: &[
+            r#"agent-browser network route "**/api/*" --abort"#,
+            r#"agent-browser network route "**/data.json" --body '{"mock": true}'"#,
+            "agent-browser network unroute",
+            "agent-browser network requests",
+            r#"agent-browser network requests --filter "api""#,
+            "agent-browser network requests --clear",
+        ],
+        ..EMPTY
+    },
+    // === Storage & state ===
+    Command {
+        name: "cookies",
+        group: STORAGE,
+        summary: "Manage browser cookies",
+        usage: "cookies [get|set|clear] [args...]",
+        description: "Manage browser cookies for the current context.",
+        subcommands: &[
+            Command {
+                name: "get",
+                summary: "Get all cookies (default)",
+                usage: "cookies get",
+                build: Some(commands::parse_cookies),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "set",
+                summary: "Set a cookie with optional properties. Without --url, --domain\n\
+                          and --path it is set for the current page URL.",
+                usage: "cookies set <name> <value> [--url <url>] [--domain <domain>] [--path <path>] [--httpOnly] [--secure] [--sameSite <Strict|Lax|None>] [--expires <timestamp>]",
+                args: &[arg("name", "Cookie name"), arg("value", "Cookie value")],
+                flags: &[
+                    valued(
+                        &["--url"],
+                        "<url>",
+                        "URL for the cookie (allows setting before page load)",
+                    ),
+                    valued(
+                        &["--domain"],
+                        "<domain>",
+                        r#"Cookie domain (e.g., ".example.com")"#,
+                    ),
+                    valued(&["--path"], "<path>", r#"Cookie path (e.g., "/api")"#),
+                    switch(
+                        &["--httpOnly"],
+                        "Set HttpOnly flag (prevents JavaScript access)",
+                    ),
+                    switch(&["--secure"], "Set Secure flag (HTTPS only)"),
+                    valued(&["--sameSite"], "<Strict|Lax|None>", "SameSite policy"),
+                    valued(
+                        &["--expires"],
+                        "<timestamp>",
+                        "Expiration time (Unix timestamp in seconds)",
+                    ),
+                ],
+                build: Some(commands::parse_cookies),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "clear",
+                summary: "Clear all cookies",
+                usage: "cookies clear",
+                build: Some(commands::parse_cookies),
+                ..SUBCOMMAND
+            },
+        ],
+        default_subcommand: Some("get"),
+        sections: &[section(
+            "Setting cookies before loading a page",
+            "With --url, a cookie can be set before the page is opened, for example to\n\
+             start already logged in.",
+        )],
+        examples: &[
+            "agent-browser cookies",
+            r#"agent-browser cookies set session_id "abc123""#,
+            r#"agent-browser cookies set session_id "abc123" --url https://app.example.com"#,
+            r#"agent-browser cookies set auth_token "xyz789" --domain example.com --path /api --httpOnly --secure"#,
+            r#"agent-browser cookies set tracking_consent "yes" --sameSite Strict"#,
+            r#"agent-browser cookies set temp_token "temp123" --expires 1735689600"#,
+            "agent-browser cookies clear",
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "storage",
+        group: STORAGE,
+        summary: "Manage web storage",
+        usage: "storage <local|session> [get|set|clear] [key] [value]",
+        description: "Manage localStorage and sessionStorage.",
+        subcommands: &[
+            Command {
+                name: "local",
+                summary: "localStorage",
+                usage: "storage local [get|set|clear] [key] [value]",
+                args: STORAGE_ARGS,
+                build: Some(commands::parse_storage),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "session",
+                summary: "sessionStorage",
+                usage: "storage session [get|set|clear] [key] [value]",
+                args: STORAGE_ARGS,
+                build: Some(commands::parse_storage),
+                ..SUBCOMMAND
+            },
+        ],
+        sections: &[section(
+            "Operations",
+            "get [key]            Get all storage or specific key\n\
+             set <key> <value>    Set a key-value pair\n\
+             clear                Clear all storage",
+        )],
+        examples: &[
+            "agent-browser storage local",
+            "agent-browser storage local get authToken",
+            r#"agent-browser storage local set theme "dark""#,
+            "agent-browser storage local clear",
+            "agent-browser storage session get userId",
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "state",
+        group: STORAGE,
+        summary: "Save or load browser state",
+        usage: "state <save|load> <path>",
+        description: "Save or restore browser state (cookies, localStorage, sessionStorage).",
+        subcommands: &[
+            Command {
+                name: "save",
+                summary: "Save current state to file",
+                usage: "state save <path>",
+                args: &[arg("path", "File to write")],
+                build: Some(commands::parse_state),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "load",
+                summary: "Note: State must be loaded at browser launch via --state flag",
+                usage: "state load <path>",
+                args: &[arg("path", "File to read")],
+                build: Some(commands::parse_state),
+                ..SUBCOMMAND
+            },
+        ],
+        sections: &[section(
+            "Applying State",
+            "Use --state flag when launching browser to load saved state:\n\
+             agent-browser --state ./auth-state.json open https://example.com\n\
+             \n\
+             Or set AGENT_BROWSER_STATE environment variable.",
+        )],
+        examples: &[
+            "agent-browser state save ./auth-state.json",
+            "agent-browser --state ./auth-state.json open https://example.com",
+        ],
+        ..EMPTY
+    },
+    // === Tabs, windows & frames ===
+    Command {
+        name: "tab",
+        group: TABS,
+        summary: "Manage browser tabs",
+        usage: "tab [list|new|close|<index>] [args...]",
+        description: "Manage browser tabs in the current window. A number switches to that tab.",
+        args: &[optional("index", "Tab to switch to")],
+        subcommands: &[
+            Command {
+                name: "list",
+                summary: "List all tabs (default)",
+                usage: "tab list",
+                build: Some(commands::parse_tab),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "new",
+                summary: "Open new tab",
+                usage: "tab new [url]",
+                args: &[optional("url", "Page to open in it")],
+                build: Some(commands::parse_tab),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "close",
+                summary: "Close tab (current if no index)",
+                usage: "tab close [index]",
+                args: &[optional("index", "Tab to close")],
+                build: Some(commands::parse_tab),
+                ..SUBCOMMAND
+            },
+        ],
+        default_subcommand: Some("list"),
+        examples: &[
+            "agent-browser tab",
+            "agent-browser tab list",
+            "agent-browser tab new",
+            "agent-browser tab new https://example.com",
+            "agent-browser tab 2",
+            "agent-browser tab close",
+            "agent-browser tab close 1",
+        ],
+        build: Some(commands::parse_tab),
+        ..EMPTY
+    },
+    Command {
+        name: "window",
+        group: TABS,
+        summary: "Manage browser windows",
+        usage: "window <new>",
+        description: "Manage browser windows.",
+        subcommands: &[Command {
+            name: "new",
+            summary: "Open new browser window",
+            usage: "window new",
+            build: Some(commands::parse_window),
+            ..SUBCOMMAND
+        }],
+        examples: &["agent-browser window new"],
+        ..EMPTY
+    },
+    Command {
+        name: "frame",
+        group: TABS,
+        summary: "Switch frame context",
+        usage: "frame <selector|main>",
+        description: "Switch to an iframe or back to the main frame.",
+        args: &[arg(
+            "selector|main",
+            "CSS selector for the iframe, or main to switch back to the main frame",
+        )],
+        examples: &[
+            r##"agent-browser frame "#embed-iframe""##,
+            r#"agent-browser frame "iframe[name='content']""#,
+            "agent-browser frame main",
+        ],
+        build: Some(commands::parse_frame),
+        ..EMPTY
+    },
+    Command {
+        name: "dialog",
+        group: TABS,
+        summary: "Handle browser dialogs",
+        usage: "dialog <accept|dismiss> [text]",
+        description: "Respond to browser dialogs (alert, confirm, prompt).",
+        subcommands: &[
+            Command {
+                name: "accept",
+                summary: "Accept dialog, optionally with prompt text",
+                usage: "dialog accept [text]",
+                args: &[optional("text", "Answer to a prompt")],
+                build: Some(commands::parse_dialog),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "dismiss",
+                summary: "Dismiss/cancel dialog",
+                usage: "dialog dismiss",
+                build: Some(commands::parse_dialog),
+                ..SUBCOMMAND
+            },
+        ],
+        examples: &[
+            "agent-browser dialog accept",
+            r#"agent-browser dialog accept "my input""#,
+            "agent-browser dialog dismiss",
+        ],
+        ..EMPTY
+    },
+    // === Debug ===
+    Command {
+        name: "trace",
+        group: DEBUG,
+        summary: "Record an execution trace",
+        usage: "trace <start|stop> [path]",
+        description: "Record a trace for debugging with Playwright Trace Viewer.",
+        subcommands: &[
+            Command {
+                name: "start",
+                summary: "Start recording trace",
+                usage: "trace start",
+                build: Some(commands::parse_trace),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "stop",
+                summary: "Stop recording and save trace",
+                usage: "trace stop <path>",
+                args: &[arg("path", "File to save the trace to")],
+                build: Some(commands::parse_trace),
+                ..SUBCOMMAND
+            },
+        ],
+        examples: &[
+            "agent-browser trace start",
+            "agent-browser trace stop ./debug-trace.zip",
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "record",
+        group: DEBUG,
+        summary: "Record the browser to video (WebM)",
+        usage: "record <start|stop|restart> [path] [url]",
+        description: "Record the browser to a WebM video file using Playwright's native recording.\n\
+                      Creates a fresh browser context but preserves cookies and localStorage.\n\
+                      If no URL is provided, automatically navigates to your current page.",
+        subcommands: &[
+            Command {
+                name: "start",
+                summary: "Start recording (defaults to current URL if omitted)",
+                usage: "record start <output.webm> [url]",
+                args: RECORD_ARGS,
+                build: Some(commands::parse_record),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "stop",
+                summary: "Stop recording and save video",
+                usage: "record stop",
+                build: Some(commands::parse_record),
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "restart",
+                summary: "Stop current recording (if any) and start a new one",
+                usage: "record restart <output.webm> [url]",
+                args: RECORD_ARGS,
+                build: Some(commands::parse_record),
+                ..SUBCOMMAND
+            },
+        ],
+        sections: &[section(
+            "Workflow",
+            "Explore the page and plan the actions first (snapshot -i), then start\n\
+             recording, run them and stop. Recording from the current page keeps its\n\
+             login state.",
+        )],
+        examples: &[
+            "agent-browser record start ./demo.webm",
+            "agent-browser record stop",
+            "agent-browser record start ./demo.webm https://example.com",
+            "agent-browser record restart ./take2.webm",
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "console",
+        group: DEBUG,
+        summary: "View console logs (--clear to empty the buffer)",
+        usage: "console [--clear]",
+        description: "View browser console output (log, warn, error, info).",
+        flags: &[switch(&["--clear"], "Clear console log buffer")],
+        examples: &["agent-browser console", "agent-browser console --clear"],
+        build: Some(commands::parse_console),
+        ..EMPTY
+    },
+    Command {
+        name: "errors",
+        group: DEBUG,
+        summary: "View page errors (--clear to empty the buffer)",
+        usage: "errors [--clear]",
+        description: "View JavaScript errors and uncaught exceptions.",
+        flags: &[switch(&["--clear"], "Clear error buffer")],
+        examples: &["agent-browser errors", "agent-browser errors --clear"],
+        build: Some(commands::parse_console),
+        ..EMPTY
+    },
+    Command {
+        name: "events",
+        group: DEBUG,
+        summary: "Show or stream console/error/request/dialog events",
+        usage: "events [console|error|request|dialog] [--follow] [--until <text>] [--timeout <ms>]",
+        description: "Shows buffered console messages, page errors and tracked requests. With\n\
+                      --follow, keeps the connection open and prints events as they happen;\n\
+                      --timeout then stops following after <ms> (exit 1 if --until never\n\
+                      matched) and --json prints one object per event.",
+        args: &[Arg {
+            choices: EVENT_KINDS,
+            ..optional_rest(
+                "kinds",
+                "console, error, request or dialog, comma or space separated (default: all)",
+            )
+        }],
+        flags: &[
+            switch(&["--follow", "-F"], "Stream new events until interrupted"),
+            valued(&["--until"], "<text>", "Stop after an event containing <text>"),
+        ],
+        globals: &["--timeout", "--json", "--session"],
+        examples: &[
+            "agent-browser events",
+            "agent-browser events console,error --follow",
+            "agent-browser events request --follow --until /api/checkout --timeout 10000",
+            "agent-browser events --follow --json",
+        ],
+        build: Some(commands::parse_events),
+        ..EMPTY
+    },
+    Command {
+        name: "highlight",
+        group: DEBUG,
+        summary: "Highlight an element",
+        usage: "highlight <selector>",
+        description: "Visually highlights an element on the page for debugging.",
+        args: &[SELECTOR],
+        examples: &[
+            r##"agent-browser highlight "#target-element""##,
+            "agent-browser highlight @e5",
+        ],
+        build: Some(commands::parse_selector_action),
+        ..EMPTY
+    },
+    // === Sessions ===
+    Command {
+        name: "session",
+        group: SESSIONS,
+        summary: "Show the current session, or manage sessions",
+        usage: "session [list|kill|prune|clone]",
+        description: "Manage isolated browser sessions. Each session has its own browser\n\
+                      instance with separate cookies, storage, and state. Without an\n\
+                      operation, prints the current session name.",
+        subcommands: &[
+            Command {
+                name: "list",
+                summary: "List active sessions with pid, uptime, idle time, launch\n\
+                          mode, tab count and the active tab's URL and title",
+                usage: "session list",
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "kill",
+                summary: "Stop a session's daemon: close, then SIGTERM, then SIGKILL",
+                usage: "session kill <name>|--all",
+                args: &[optional("name", "Session to stop")],
+                flags: &[switch(&["--all"], "Stop every local session")],
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "prune",
+                summary: "Remove pid/socket files left by daemons that died",
+                usage: "session prune [--dry-run]",
+                flags: &[switch(&["--dry-run"], "Only list what would be removed")],
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "clone",
+                summary: "Start session <to> with <from>'s cookies and storage",
+                usage: "session clone <from> <to> [--tabs]",
+                args: &[
+                    arg("from", "Session to copy"),
+                    arg("to", "New session to start"),
+                ],
+                flags: &[switch(&["--tabs"], "Also reopen <from>'s open tabs in <to>")],
+                ..SUBCOMMAND
+            },
+        ],
+        local: true,
+        globals: &["--json", "--session", "--timeout"],
+        sections: &[section(
+            "Environment",
+            "AGENT_BROWSER_SESSION    Default session name",
+        )],
+        examples: &[
+            "agent-browser session",
+            "agent-browser session list",
+            "agent-browser session kill agent1",
+            "agent-browser session prune --dry-run",
+            "agent-browser session clone auth worker1 --tabs",
+            "agent-browser --session test open example.com",
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "daemon",
+        group: SESSIONS,
+        summary: "Inspect or control the session daemon",
+        usage: "daemon <status|stop|restart|logs>",
+        description: "The daemon's stdout and stderr go to <session>.log in the socket directory\n\
+                      (or --log-file). Logs over 5 MB are rotated to <log>.1 when the daemon starts.",
+        subcommands: &[
+            Command {
+                name: "status",
+                summary: "Show pid, uptime, socket, launch options and whether\n\
+                          the daemon is responding (exit 1 if it is not)",
+                usage: "daemon status",
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "stop",
+                summary: "Stop the daemon (SIGTERM) and remove its pid and socket files",
+                usage: "daemon stop [--force]",
+                flags: &[switch(&["--force"], "Send SIGKILL instead")],
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "restart",
+                summary: "Stop and start the daemon with the same launch options",
+                usage: "daemon restart",
+                ..SUBCOMMAND
+            },
+            Command {
+                name: "logs",
+                summary: "Show the last lines of the daemon log",
+                usage: "daemon logs [-n <lines>]",
+                flags: &[valued(
+                    &["--lines", "-n"],
+                    "<lines>",
+                    "How many lines (default: 50)",
+                )],
+                ..SUBCOMMAND
+            },
+        ],
+        local: true,
+        examples: &[
+            "agent-browser daemon status",
+            "agent-browser --session agent1 daemon stop --force",
+            "agent-browser daemon restart",
+            "agent-browser daemon logs -n 100",
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "config",
+        group: SESSIONS,
+        summary: "Show each setting's value and where it came from",
+        usage: "config show [--json]",
+        description: "Lists every launch and global setting with its resolved value and where it\n\
+                      came from: a command-line flag, an environment variable, a config file (and\n\
+                      preset), or the default. Proxy and CDP passwords, the daemon token and\n\
+                      credential-like headers are masked.\n\
+                      \n\
+                      Other flags on the command line are applied first, so\n\
+                      'agent-browser --preset ci config show' shows what --preset ci would use.",
+        subcommands: &[Command {
+            name: "show",
+            summary: "List the effective settings",
+            usage: "config show",
+            ..SUBCOMMAND
+        }],
+        local: true,
+        globals: &["--json", "--preset"],
+        examples: &[
+            "agent-browser config show",
+            "agent-browser --preset ci config show --json",
+        ],
+        ..EMPTY
+    },
+    // === Batch & shell ===
+    Command {
+        name: "batch",
+        group: BATCH,
+        summary: "Run newline-delimited commands (file or stdin)",
+        usage: "batch [file] [--bail]",
+        description: "Reads newline-delimited commands from a file (or stdin when omitted or \"-\")\n\
+                      and sends them to the session over a single daemon connection. One response\n\
+                      is printed per command.\n\
+                      \n\
+                      Each line is either CLI-style arguments (quoted like a shell) or a raw JSON\n\
+                      request object. Blank lines and lines starting with # are skipped.\n\
+                      \n\
+                      Exit status is 0 if every command succeeded, 1 otherwise.",
+        args: &[optional("file", "Commands to run (default: stdin)")],
+        flags: &[switch(&["--bail"], "Stop at the first failing command")],
+        local: true,
+        sections: &[section(
+            "Piping commands",
+            "printf 'open example.com\\nsnapshot -i\\n' | agent-browser batch --json\n\
+             echo '{\"action\":\"url\"}' | agent-browser batch",
+        )],
+        examples: &[
+            "agent-browser batch script.txt",
+            "agent-browser batch --bail < steps.txt",
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "shell",
+        group: BATCH,
+        summary: "Interactive shell with history and completion",
+        usage: "shell",
+        description: "Starts a REPL that sends each line to the session daemon over one persistent\n\
+                      connection. Lines are parsed exactly like CLI arguments (or taken as raw JSON\n\
+                      requests). Command history is kept in the socket directory, and Tab completes\n\
+                      command and subcommand names.",
+        local: true,
+        sections: &[section(
+            "Shell Commands",
+            "help [command]       Show help for all commands or one command\n\
+             .session             Show the current session\n\
+             .session <name>      Switch to another session (starting its daemon if needed)\n\
+             .exit, .quit         Leave the shell (Ctrl-D also works)",
+        )],
+        examples: &["agent-browser shell", "agent-browser --session test shell"],
+        ..EMPTY
+    },
+    // === iOS ===
+    Command {
+        name: "tap",
+        group: IOS,
+        summary: "Tap an element (touch gesture)",
+        usage: "tap <selector>",
+        description: "Taps an element. This is an alias for 'click' that provides semantic clarity\n\
+                      for touch-based interfaces like iOS Safari.",
+        args: &[SELECTOR],
+        examples: &[
+            r##"agent-browser tap "#submit-button""##,
+            "agent-browser tap @e1",
+            r#"agent-browser -p ios tap "button:has-text('Sign In')""#,
+        ],
+        build: Some(commands::parse_selector_action),
+        ..EMPTY
+    },
+    Command {
+        name: "swipe",
+        group: IOS,
+        summary: "Swipe gesture (iOS)",
+        usage: "swipe <up|down|left|right> [distance]",
+        description: "Performs a swipe gesture on iOS Safari. The direction determines\n\
+                      which way the content moves (swipe up scrolls down, etc.).",
+        args: &[
+            Arg {
+                choices: &["up", "down", "left", "right"],
+                ..arg("direction", "up, down, left, or right")
+            },
+            optional("distance", "Distance in pixels (default: 300)"),
+        ],
+        examples: &[
+            "agent-browser -p ios swipe up",
+            "agent-browser -p ios swipe down 500",
+            "agent-browser -p ios swipe left",
+        ],
+        build: Some(commands::parse_swipe),
+        ..EMPTY
+    },
+    Command {
+        name: "device",
+        group: IOS,
+        summary: "Manage iOS simulators",
+        usage: "device [list]",
+        subcommands: &[Command {
+            name: "list",
+            summary: "List available iOS simulators",
+            usage: "device list",
+            build: Some(commands::parse_device),
+            ..SUBCOMMAND
+        }],
+        default_subcommand: Some("list"),
+        examples: &[
+            "agent-browser device list",
+            "agent-browser -p ios device list",
+        ],
+        ..EMPTY
+    },
+    // === Setup ===
+    Command {
+        name: "install",
+        group: SETUP,
+        summary: "Install browser binaries (--with-deps: also system dependencies)",
+        usage: "install [--with-deps]",
+        description: "Downloads and installs browser binaries required for automation.",
+        flags: &[switch(
+            &["--with-deps", "-d"],
+            "Also install system dependencies (Linux only)",
+        )],
+        local: true,
+        globals: &[],
+        examples: &[
+            "agent-browser install",
+            "agent-browser install --with-deps",
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "completions",
+        group: SETUP,
+        summary: "Print a bash, zsh or fish completion script",
+        usage: "completions <bash|zsh|fish>",
+        description: "Prints a script that completes command names, subcommands and flags, and\n\
+                      session names after --session and 'session kill'. The script is generated\n\
+                      from the CLI's own command tables, so regenerate it after upgrading.",
+        args: &[Arg {
+            choices: SHELLS,
+            ..arg("shell", "bash, zsh or fish")
+        }],
+        local: true,
+        globals: &[],
+        sections: &[section(
+            "Loading for the current shell only",
+            "source <(agent-browser completions bash)",
+        )],
+        examples: &[
+            "agent-browser completions bash > ~/.local/share/bash-completion/completions/agent-browser",
+            r#"agent-browser completions zsh > "${fpath[1]}/_agent-browser""#,
+            "agent-browser completions fish > ~/.config/fish/completions/agent-browser.fish",
+        ],
+        ..EMPTY
+    },
+    Command {
+        name: "help",
+        group: SETUP,
+        summary: "Show help for all commands or one command",
+        usage: "help [command] [--json]",
+        description: "With --json, prints the command catalog: every command with its\n\
+                      arguments, flags, subcommands and examples, plus the global flags.",
+        args: &[optional("command", "Command to describe")],
+        local: true,
+        globals: &["--json"],
+        examples: &[
+            "agent-browser help",
+            "agent-browser help snapshot",
+            "agent-browser help --json",
+            "agent-browser help cookies --json",
+        ],
+        ..EMPTY
+    },
+];
+
+const STORAGE_ARGS: &[Arg] = &[
+    Arg {
+        choices: &["get", "set", "clear"],
+        ..optional("operation", "get, set or clear (default: get)")
+    },
+    optional("key", "Key to get or set"),
+    optional("value", "Value to set"),
+];
+
+const RECORD_ARGS: &[Arg] = &[
+    arg("output.webm", "Video file to write"),
+    optional("url", "Page to record (default: the current page)"),
+];
+
+/// The command or alias `name`
+pub fn find(name: &str) -> Option<&'static Command> {
+    find_in(REGISTRY, name)
+}
+
+/// The subcommand or alias `name` of `command`
+pub fn find_subcommand(command: &'static Command, name: &str) -> Option<&'static Command> {
+    find_in(command.subcommands, name)
+}
+
+fn find_in(commands: &'static [Command], name: &str) -> Option<&'static Command> {
+    commands
+        .iter()
+        .find(|cmd| cmd.name == name || cmd.aliases.contains(&name))
+}
+
+/// Every command name, aliases included
+pub fn command_names() -> Vec<&'static str> {
+    REGISTRY
+        .iter()
+        .flat_map(|cmd| std::iter::once(cmd.name).chain(cmd.aliases.iter().copied()))
+        .collect()
+}
+
+/// The subcommand names (and aliases) of `command`
+pub fn subcommand_names(command: &Command) -> Vec<&'static str> {
+    command
+        .subcommands
+        .iter()
+        .flat_map(|sub| std::iter::once(sub.name).chain(sub.aliases.iter().copied()))
+        .collect()
+}
+
+/// Words that can follow `command`: its subcommands, or the choices for its
+/// first argument
+pub fn next_words(command: &str) -> Vec<&'static str> {
+    let Some(cmd) = find(command) else {
+        return Vec::new();
+    };
+    if !cmd.subcommands.is_empty() {
+        return subcommand_names(cmd);
+    }
+    cmd.args
+        .first()
+        .map_or_else(Vec::new, |a| a.choices.to_vec())
+}
+
+/// The flags of a command and all of its subcommands
+pub fn flags_of(command: &'static Command) -> Vec<&'static FlagSpec> {
+    let mut flags: Vec<&FlagSpec> = command.flags.iter().collect();
+    for sub in command.subcommands {
+        for spec in sub.flags {
+            if !flags.iter().any(|f| f.names == spec.names) {
+                flags.push(spec);
+            }
+        }
+    }
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_commands() -> Vec<&'static Command> {
+        REGISTRY
+            .iter()
+            .flat_map(|cmd| std::iter::once(cmd).chain(cmd.subcommands))
+            .collect()
+    }
+
+    #[test]
+    fn test_names_are_unique() {
+        let mut names = command_names();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count);
+        for cmd in REGISTRY {
+            let mut subs = subcommand_names(cmd);
+            let count = subs.len();
+            subs.sort_unstable();
+            subs.dedup();
+            assert_eq!(subs.len(), count, "duplicate subcommand in {}", cmd.name);
+        }
+    }
+
+    #[test]
+    fn test_command_flags_are_unique_per_command() {
+        for cmd in REGISTRY {
+            let mut names: Vec<&str> = flags_of(cmd)
+                .iter()
+                .flat_map(|s| s.names.iter().copied())
+                .collect();
+            let count = names.len();
+            names.sort_unstable();
+            names.dedup();
+            assert_eq!(names.len(), count, "duplicate flag in {}", cmd.name);
+        }
+    }
+
+    #[test]
+    fn test_usage_shows_required_args() {
+        for cmd in all_commands() {
+            assert!(!cmd.summary.is_empty(), "{} has no summary", cmd.name);
+            assert!(
+                cmd.usage.split(' ').any(|word| word == cmd.name),
+                "usage of {} doesn't name it",
+                cmd.name
+            );
+            let required = cmd.args.iter().filter(|a| a.required).count();
+            assert!(
+                cmd.usage.matches('<').count() >= required,
+                "usage of {} doesn't show its required arguments",
+                cmd.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_daemon_command_has_a_builder() {
+        for cmd in REGISTRY {
+            assert!(!cmd.group.is_empty(), "{} has no group", cmd.name);
+            if cmd.local {
+                assert!(cmd.build.is_none(), "{} is local", cmd.name);
+                continue;
+            }
+            if cmd.subcommands.is_empty() {
+                assert!(cmd.build.is_some(), "{} has no builder", cmd.name);
+            }
+            for sub in cmd.subcommands {
+                assert!(
+                    sub.build.is_some(),
+                    "{} {} has no builder",
+                    cmd.name,
+                    sub.name
+                );
+            }
+            if let Some(default) = cmd.default_subcommand {
+                assert!(find_subcommand(cmd, default).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_global_names_exist() {
+        for cmd in REGISTRY {
+            for name in cmd.globals {
+                assert!(
+                    crate::flags::GLOBAL_FLAGS
+                        .iter()
+                        .any(|spec| spec.names[0] == *name),
+                    "{} lists unknown global {}",
+                    cmd.name,
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_examples_are_command_lines() {
+        let commands = REGISTRY
+            .iter()
+            .chain(REGISTRY.iter().flat_map(|cmd| cmd.subcommands));
+        for cmd in commands {
+            for example in cmd.examples {
+                assert!(
+                    example.starts_with("agent-browser ") && !example.contains(" # "),
+                    "{}: example is not a command line: {:?}",
+                    cmd.name,
+                    example
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_next_words() {
+        assert_eq!(next_words("dialog"), vec!["accept", "dismiss"]);
+        assert!(next_words("set").contains(&"geolocation"));
+        assert_eq!(next_words("completions"), SHELLS.to_vec());
+        assert!(next_words("click").is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+use std::process::Command;
+
+use crate::batch::split_line;
+
+/// Oldest Node.js major version the daemon supports (Playwright's minimum)
+const MIN_NODE_MAJOR: u32 = 18;
+/// Oldest Bun major version the daemon supports
+const MIN_BUN_MAJOR: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuntimeKind {
+    Node,
+    Bun,
+}
+
+impl RuntimeKind {
+    /// Guess the runtime from the executable name (`bun`, `bun.exe`, otherwise node)
+    fn detect(program: &str) -> Self {
+        // Split on both separators so Windows paths are recognized everywhere
+        let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+        let stem = name.strip_suffix(".exe").unwrap_or(name);
+        if stem.eq_ignore_ascii_case("bun") {
+            RuntimeKind::Bun
+        } else {
+            RuntimeKind::Node
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            RuntimeKind::Node => "Node.js",
+            RuntimeKind::Bun => "Bun",
+        }
+    }
+
+    fn min_major(self) -> u32 {
+        match self {
+            RuntimeKind::Node => MIN_NODE_MAJOR,
+            RuntimeKind::Bun => MIN_BUN_MAJOR,
+        }
+    }
+}
+
+/// JavaScript runtime used to run the daemon
+#[derive(Debug)]
+pub struct Runtime {
+    pub program: String,
+    /// Extra arguments placed before the daemon script (AGENT_BROWSER_NODE_ARGS)
+    pub args: Vec<String>,
+}
+
+/// Parse `v20.11.1` (node) or `1.1.8` (bun) version output
+fn parse_version(output: &str) -> Option<(u32, u32, u32)> {
+    let text = output.trim();
+    let text = text.strip_prefix('v').unwrap_or(text);
+    let mut parts = text
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|p| p.parse::<u32>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// Find the runtime to spawn the daemon with and check that it is recent enough.
+///
+/// `node_path` comes from `--node-path` / `AGENT_BROWSER_NODE` (default `node`
+/// from PATH); `node_args` from `AGENT_BROWSER_NODE_ARGS`, split like a shell.
+pub fn resolve_runtime(
+    node_path: Option<&str>,
+    node_args: Option<&str>,
+) -> Result<Runtime, String> {
+    let program = node_path.unwrap_or("node").to_string();
+    let kind = RuntimeKind::detect(&program);
+    let args = match node_args {
+        Some(a) => split_line(a).map_err(|e| format!("Invalid AGENT_BROWSER_NODE_ARGS: {}", e))?,
+        None => Vec::new(),
+    };
+
+    let output = Command::new(&program)
+        .arg("--version")
+        .output()
+        .map_err(|e| {
+            format!(
+                "JavaScript runtime '{}' could not be run: {}\n\
+             Install Node.js {}+ or point AGENT_BROWSER_NODE / --node-path at a node binary.",
+                program, e, MIN_NODE_MAJOR
+            )
+        })?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = parse_version(&stdout)
+        .filter(|_| output.status.success())
+        .ok_or_else(|| {
+            format!(
+                "Could not determine the version of '{}' (got '{}')",
+                program,
+                stdout.trim()
+            )
+        })?;
+
+    check_version(kind, version, &program)?;
+    Ok(Runtime { program, args })
+}
+
+fn check_version(kind: RuntimeKind, version: (u32, u32, u32), program: &str) -> Result<(), String> {
+    if version.0 < kind.min_major() {
+        return Err(format!(
+            "{} {}.{}.{} at '{}' is too old; agent-browser needs {} {} or newer.\n\
+             Upgrade it or set AGENT_BROWSER_NODE / --node-path to a newer runtime.",
+            kind.name(),
+            version.0,
+            version.1,
+            version.2,
+            program,
+            kind.name(),
+            kind.min_major()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v20.11.1\n"), Some((20, 11, 1)));
+        assert_eq!(parse_version("1.1.8"), Some((1, 1, 8)));
+        assert_eq!(parse_version("v22.0.0-nightly2024"), Some((22, 0, 0)));
+        assert_eq!(parse_version("v18"), Some((18, 0, 0)));
+        assert_eq!(parse_version("not a version"), None);
+        assert_eq!(parse_version(""), None);
+    }
+
+    #[test]
+    fn test_detect_kind() {
+        assert_eq!(RuntimeKind::detect("node"), RuntimeKind::Node);
+        assert_eq!(
+            RuntimeKind::detect("/home/me/.nvm/versions/node/v20.11.1/bin/node"),
+            RuntimeKind::Node
+        );
+        assert_eq!(RuntimeKind::detect("/usr/local/bin/bun"), RuntimeKind::Bun);
+        assert_eq!(RuntimeKind::detect("C:\\tools\\bun.exe"), RuntimeKind::Bun);
+    }
+
+    #[test]
+    fn test_check_version() {
+        assert!(check_version(RuntimeKind::Node, (18, 0, 0), "node").is_ok());
+        assert!(check_version(RuntimeKind::Node, (22, 3, 0), "node").is_ok());
+        let err = check_version(RuntimeKind::Node, (16, 20, 2), "node").unwrap_err();
+        assert!(err.contains("Node.js 16.20.2"));
+        assert!(err.contains("18 or newer"));
+        assert!(check_version(RuntimeKind::Bun, (1, 0, 0), "bun").is_ok());
+        assert!(check_version(RuntimeKind::Bun, (0, 8, 1), "bun").is_err());
+    }
+
+    #[test]
+    fn test_missing_runtime() {
+        let err = resolve_runtime(Some("/nonexistent/agent-browser-node"), None).unwrap_err();
+        assert!(err.contains("could not be run"));
+        assert!(err.contains("AGENT_BROWSER_NODE"));
+    }
+
+    #[test]
+    fn test_invalid_node_args() {
+        let err = resolve_runtime(None, Some("--inspect '")).unwrap_err();
+        assert!(err.contains("AGENT_BROWSER_NODE_ARGS"));
+    }
+}
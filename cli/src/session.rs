@@ -0,0 +1,913 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::color;
+use crate::commands::{gen_id, ParseError};
+use crate::connection::{
+    cleanup_stale_files, daemon_endpoint, daemon_started, get_socket_dir, lock_session, pid_alive,
+    read_launch_record, read_pid, record_launch, record_spawn, send_command, try_lock_session,
+    ConnectOptions, StartupLock,
+};
+use crate::daemon::{format_duration, stop_process, wait_for_exit};
+use crate::flags::Flags;
+use crate::launch::prepare_local_daemon;
+use crate::output::{print_error, print_json, print_parse_error};
+use crate::registry;
+
+/// How long `session kill` waits for a daemon to exit after `close`
+const CLOSE_WAIT: Duration = Duration::from_secs(3);
+
+/// Names of sessions whose daemon process is alive on this machine
+pub fn local_sessions() -> Vec<String> {
+    let socket_dir = get_socket_dir();
+    let mut sessions: Vec<String> = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(&socket_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Look for pid files in socket directory
+            if name.ends_with(".pid") {
+                let session_name = name.strip_suffix(".pid").unwrap_or("");
+                if !session_name.is_empty() {
+                    // Check if session is actually running
+                    if read_pid(session_name).is_some_and(pid_alive) {
+                        sessions.push(session_name.to_string());
+                    }
+                }
+            }
+        }
+    }
+    sessions
+}
+
+/// How long `session list` waits for each daemon's status by default, in
+/// milliseconds (`--timeout` overrides it)
+const STATUS_TIMEOUT_MS: u64 = 2000;
+
+/// One entry of `session list`
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct SessionInfo {
+    name: String,
+    current: bool,
+    pid: Option<u32>,
+    /// Daemon start time (pid file mtime), Unix milliseconds
+    started: Option<u64>,
+    socket: Option<String>,
+    /// Whether the daemon answered within the timeout
+    responsive: bool,
+    idle: Option<u64>,
+    /// `None` until a browser has been launched
+    browser: Option<BrowserInfo>,
+}
+
+/// The `browser` part of a `daemon_status` reply
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct BrowserInfo {
+    /// "local", "cdp", "provider" or "ios"
+    mode: String,
+    headed: bool,
+    tabs: u64,
+    url: Option<String>,
+    title: Option<String>,
+}
+
+impl SessionInfo {
+    /// Fill in the live fields from a `daemon_status` reply
+    fn apply_status(&mut self, status: &Value) {
+        self.responsive = true;
+        self.idle = status.get("idle").and_then(|v| v.as_u64());
+        self.browser = status
+            .get("browser")
+            .and_then(|b| serde_json::from_value(b.clone()).ok());
+    }
+
+    /// Details shown after the name in the human-readable listing
+    fn describe(&self, now: SystemTime) -> String {
+        let mut parts = Vec::new();
+        if let Some(pid) = self.pid {
+            parts.push(format!("pid {}", pid));
+        }
+        if let Some(started) = self.started {
+            let started = UNIX_EPOCH + Duration::from_millis(started);
+            if let Ok(up) = now.duration_since(started) {
+                parts.push(format!("up {}", format_duration(up)));
+            }
+        }
+        if !self.responsive {
+            parts.push("not responding".to_string());
+            return parts.join(", ");
+        }
+        if let Some(ms) = self.idle {
+            parts.push(format!(
+                "idle {}",
+                format_duration(Duration::from_millis(ms))
+            ));
+        }
+        match &self.browser {
+            None => parts.push("no browser".to_string()),
+            Some(b) => {
+                let mode = match b.mode.as_str() {
+                    "local" if b.headed => "headed".to_string(),
+                    "local" => "headless".to_string(),
+                    other => other.to_string(),
+                };
+                parts.push(mode);
+                parts.push(format!(
+                    "{} tab{}",
+                    b.tabs,
+                    if b.tabs == 1 { "" } else { "s" }
+                ));
+            }
+        }
+        parts.join(", ")
+    }
+}
+
+/// Ask one daemon for its status, without retries or the version check
+fn query_status(session: &str, options: &ConnectOptions) -> Option<Value> {
+    let cmd = json!({ "id": gen_id(), "action": "daemon_status" });
+    let resp = send_command(cmd, session, options).ok()?;
+    if !resp.success {
+        return None;
+    }
+    resp.data
+}
+
+/// Query every session's daemon in parallel. Daemons that haven't answered
+/// by the deadline are left out, so one hung session can't stall the list.
+fn query_statuses(flags: &Flags, sessions: &[String]) -> HashMap<String, Value> {
+    let timeout = flags.timeout.unwrap_or(STATUS_TIMEOUT_MS);
+    let mut options = ConnectOptions::from_flags(flags);
+    options.timeout = Some(timeout);
+    options.retry.retries = 0;
+    options.skip_version_check = true;
+
+    let (tx, rx) = mpsc::channel();
+    for session in sessions {
+        let (tx, options, session) = (tx.clone(), options.clone(), session.clone());
+        thread::spawn(move || {
+            let status = query_status(&session, &options);
+            let _ = tx.send((session, status));
+        });
+    }
+    drop(tx);
+
+    let deadline = Instant::now() + Duration::from_millis(timeout);
+    let mut statuses = HashMap::new();
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(left) {
+            Ok((session, Some(status))) => {
+                statuses.insert(session, status);
+            }
+            Ok((_, None)) => {}
+            Err(_) => break,
+        }
+    }
+    statuses
+}
+
+/// Ask a remote daemon (`--daemon-url`) which sessions run on its host
+fn remote_sessions(flags: &Flags) -> Result<Vec<String>, (String, i32)> {
+    let cmd = json!({ "id": gen_id(), "action": "session_list" });
+    match send_command(cmd, &flags.session, &ConnectOptions::from_flags(flags)) {
+        Ok(resp) if resp.success => Ok(resp
+            .data
+            .as_ref()
+            .and_then(|d| d.get("sessions"))
+            .and_then(|v| v.as_array())
+            .map(|list| {
+                list.iter()
+                    .filter_map(|s| s.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()),
+        Ok(resp) => Err((
+            resp.error
+                .unwrap_or_else(|| "Failed to list remote sessions".to_string()),
+            1,
+        )),
+        Err(e) => Err((e.to_string(), e.exit_code())),
+    }
+}
+
+/// Run `agent-browser session [list|kill|prune]`. Returns the process exit code.
+pub fn run_session(args: &[String], flags: &Flags) -> i32 {
+    let subcommand = args.get(1).map(|s| s.as_str());
+    if flags.daemon_url.is_some()
+        && matches!(subcommand, Some("kill") | Some("prune") | Some("clone"))
+    {
+        print_error(
+            "session kill/prune/clone only manage local daemons; run them on the daemon's host",
+            flags.json,
+        );
+        return 1;
+    }
+
+    match subcommand {
+        Some("list") => list_sessions(flags),
+        Some("kill") => kill_sessions(&args[2..], flags),
+        Some("prune") => prune_sessions(args.iter().any(|a| a == "--dry-run"), flags),
+        Some("clone") => clone_session(&args[2..], flags),
+        Some(other) => {
+            print_parse_error(
+                &ParseError::UnknownSubcommand {
+                    subcommand: other.to_string(),
+                    valid_options: registry::next_words("session"),
+                },
+                flags.json,
+            );
+            1
+        }
+        None => {
+            // Just show current session
+            if flags.json {
+                print_json(true, &json!({ "session": flags.session }));
+            } else {
+                println!("{}", flags.session);
+            }
+            0
+        }
+    }
+}
+
+fn list_sessions(flags: &Flags) -> i32 {
+    let sessions = if flags.daemon_url.is_some() {
+        match remote_sessions(flags) {
+            Ok(names) => remote_session_infos(flags, names),
+            Err((msg, code)) => {
+                print_error(&msg, flags.json);
+                return code;
+            }
+        }
+    } else {
+        local_session_infos(flags)
+    };
+
+    if flags.json {
+        print_json(true, &json!({ "sessions": sessions }));
+    } else if sessions.is_empty() {
+        println!("No active sessions");
+    } else {
+        println!("Active sessions:");
+        let width = sessions.iter().map(|s| s.name.len()).max().unwrap_or(0);
+        let now = SystemTime::now();
+        for info in &sessions {
+            let marker = if info.current {
+                color::cyan("→")
+            } else {
+                " ".to_string()
+            };
+            println!(
+                "{} {:width$}  {}",
+                marker,
+                info.name,
+                color::dim(&info.describe(now)),
+                width = width
+            );
+            if let Some(url) = info.browser.as_ref().and_then(|b| b.url.as_deref()) {
+                let title = info.browser.as_ref().and_then(|b| b.title.as_deref());
+                match title.filter(|t| !t.is_empty()) {
+                    Some(title) => println!(
+                        "  {:width$}  {} {}",
+                        "",
+                        url,
+                        color::dim(&format!("({})", title)),
+                        width = width
+                    ),
+                    None => println!("  {:width$}  {}", "", url, width = width),
+                }
+            }
+        }
+    }
+    0
+}
+
+/// Details for every live local session, sorted by name
+fn local_session_infos(flags: &Flags) -> Vec<SessionInfo> {
+    let mut names = local_sessions();
+    names.sort();
+    let mut statuses = query_statuses(flags, &names);
+
+    names
+        .into_iter()
+        .map(|name| {
+            let mut info = SessionInfo {
+                current: name == flags.session,
+                pid: read_pid(&name),
+                started: daemon_started(&name)
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as u64),
+                socket: Some(daemon_endpoint(&name)),
+                ..SessionInfo::default()
+            };
+            if let Some(status) = statuses.remove(&name) {
+                info.apply_status(&status);
+            }
+            info.name = name;
+            info
+        })
+        .collect()
+}
+
+/// Sessions on a remote host. Only the daemon `--daemon-url` points at can be
+/// reached, so the others are listed by name alone.
+fn remote_session_infos(flags: &Flags, names: Vec<String>) -> Vec<SessionInfo> {
+    let mut options = ConnectOptions::from_flags(flags);
+    options.timeout = Some(flags.timeout.unwrap_or(STATUS_TIMEOUT_MS));
+    options.retry.retries = 0;
+    options.skip_version_check = true;
+    let status = query_status(&flags.session, &options);
+    let connected = status
+        .as_ref()
+        .and_then(|s| s.get("session"))
+        .and_then(|v| v.as_str())
+        .map(String::from);
+
+    names
+        .into_iter()
+        .map(|name| {
+            let mut info = SessionInfo {
+                current: name == flags.session,
+                ..SessionInfo::default()
+            };
+            if let Some(status) = status
+                .as_ref()
+                .filter(|_| connected.as_deref() == Some(&name))
+            {
+                info.pid = status.get("pid").and_then(|v| v.as_u64()).map(|p| p as u32);
+                info.apply_status(status);
+            }
+            info.name = name;
+            info
+        })
+        .collect()
+}
+
+/// Stop one session's daemon: ask it to close, then escalate to SIGTERM and
+/// SIGKILL (terminate on Windows). Returns the pid and how it was stopped, or
+/// `None` if it wasn't running.
+fn kill_session(flags: &Flags, session: &str) -> Result<Option<(u32, &'static str)>, String> {
+    let _lock = lock_session(session)?;
+    let Some(pid) = read_pid(session).filter(|&pid| pid_alive(pid)) else {
+        cleanup_stale_files(session);
+        return Ok(None);
+    };
+
+    let mut options = ConnectOptions::from_flags(flags);
+    options.timeout = Some(5000);
+    options.retry.retries = 0;
+    options.skip_version_check = true;
+    let cmd = json!({ "id": gen_id(), "action": "close" });
+    let closed = send_command(cmd, session, &options).is_ok_and(|r| r.success);
+    if closed && wait_for_exit(pid, CLOSE_WAIT) {
+        cleanup_stale_files(session);
+        return Ok(Some((pid, "close")));
+    }
+
+    if stop_process(flags, session, pid, false).is_ok() {
+        return Ok(Some((pid, "terminate")));
+    }
+    stop_process(flags, session, pid, true).map(|_| Some((pid, "kill")))
+}
+
+fn kill_sessions(args: &[String], flags: &Flags) -> i32 {
+    let targets: Vec<String> = match args.first().map(|s| s.as_str()) {
+        Some("--all") => local_sessions(),
+        Some(name) if !name.starts_with('-') => vec![name.to_string()],
+        _ => {
+            print_parse_error(
+                &ParseError::MissingArguments {
+                    context: "session kill".to_string(),
+                    usage: "session kill <name> | --all",
+                },
+                flags.json,
+            );
+            return 1;
+        }
+    };
+
+    let mut results: Vec<Value> = Vec::new();
+    let mut failed = false;
+    for name in &targets {
+        match kill_session(flags, name) {
+            Ok(Some((pid, method))) => {
+                if !flags.json {
+                    println!(
+                        "{} Killed session '{}' (pid {}, {})",
+                        color::success_indicator(),
+                        name,
+                        pid,
+                        method
+                    );
+                }
+                results.push(json!({ "session": name, "pid": pid, "method": method }));
+            }
+            Ok(None) => {
+                if !flags.json {
+                    println!(
+                        "{} Session '{}' is not running",
+                        color::success_indicator(),
+                        name
+                    );
+                }
+                results.push(json!({ "session": name, "pid": null, "method": null }));
+            }
+            Err(e) => {
+                failed = true;
+                if !flags.json {
+                    print_error(&format!("Session '{}': {}", name, e), false);
+                }
+                results.push(json!({ "session": name, "error": e }));
+            }
+        }
+    }
+
+    if flags.json {
+        print_json(!failed, &json!({ "sessions": results }));
+    } else if targets.is_empty() {
+        println!("No active sessions");
+    }
+    if failed {
+        1
+    } else {
+        0
+    }
+}
+
+/// Files a session leaves in the socket directory (logs are kept for diagnosis).
+/// `clone-state.json` is the storage state `session clone` hands to a new
+/// session; it holds credentials and is left behind if the clone is killed.
+const SESSION_FILE_EXTENSIONS: &[&str] = &[
+    "pid",
+    "sock",
+    "port",
+    "launch",
+    "options",
+    "stream",
+    "clone-state.json",
+];
+
+/// Sessions in `dir` whose daemon is gone, with the files they left behind
+fn stale_sessions(dir: &Path) -> Vec<(String, Vec<PathBuf>)> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            SESSION_FILE_EXTENSIONS.iter().find_map(|ext| {
+                let name = file_name.strip_suffix(ext)?.strip_suffix('.')?;
+                (!name.is_empty()).then(|| name.to_string())
+            })
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let files = stale_files(dir, &name)?;
+            Some((name, files))
+        })
+        .collect()
+}
+
+/// Files `name` left in `dir`, or `None` while its daemon is alive
+fn stale_files(dir: &Path, name: &str) -> Option<Vec<PathBuf>> {
+    let pid = fs::read_to_string(dir.join(format!("{}.pid", name)))
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok());
+    if pid.is_some_and(pid_alive) {
+        return None;
+    }
+    let files: Vec<PathBuf> = SESSION_FILE_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", name, ext)))
+        .filter(|p| p.exists())
+        .collect();
+    (!files.is_empty()).then_some(files)
+}
+
+/// Stale sessions in the socket directory with their startup locks held, so
+/// none can be started while its files are removed. A session being started
+/// or cloned holds its lock and only looks stale; it is left alone.
+fn lock_stale_sessions() -> Result<Vec<(String, Vec<PathBuf>, StartupLock)>, String> {
+    let dir = get_socket_dir();
+    let mut locked = Vec::new();
+    for (name, _) in stale_sessions(&dir) {
+        let Some(lock) = try_lock_session(&name)? else {
+            continue;
+        };
+        if let Some(files) = stale_files(&dir, &name) {
+            locked.push((name, files, lock));
+        }
+    }
+    Ok(locked)
+}
+
+fn prune_sessions(dry_run: bool, flags: &Flags) -> i32 {
+    let stale = match lock_stale_sessions() {
+        Ok(stale) => stale,
+        Err(e) => {
+            print_error(&e, flags.json);
+            return 1;
+        }
+    };
+
+    let mut failed = false;
+    if !dry_run {
+        for (_, files, _) in &stale {
+            for file in files {
+                if let Err(e) = fs::remove_file(file) {
+                    failed = true;
+                    print_error(
+                        &format!("Failed to remove {}: {}", file.display(), e),
+                        flags.json,
+                    );
+                }
+            }
+        }
+    }
+
+    if flags.json {
+        let pruned: Vec<Value> = stale
+            .iter()
+            .map(|(name, files, _)| {
+                json!({
+                    "session": name,
+                    "files": files.iter().map(|f| f.display().to_string()).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        print_json(!failed, &json!({ "dryRun": dry_run, "pruned": pruned }));
+    } else if stale.is_empty() {
+        println!("No stale sessions");
+    } else {
+        let verb = if dry_run { "Would remove" } else { "Removed" };
+        for (name, files, _) in &stale {
+            println!("{} stale session '{}':", verb, name);
+            for file in files {
+                println!("  {}", file.display());
+            }
+        }
+    }
+    if failed {
+        1
+    } else {
+        0
+    }
+}
+
+/// URLs of a `tab_list` reply's tabs, and the index of the active one
+fn tab_urls(data: &Value) -> (Vec<String>, usize) {
+    let urls = data
+        .get("tabs")
+        .and_then(|v| v.as_array())
+        .map(|tabs| {
+            tabs.iter()
+                .filter_map(|t| t.get("url")?.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    let active = data.get("active").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+    (urls, active)
+}
+
+/// Drop the clone's temporary state file from a launch record, so that
+/// `daemon restart` doesn't look for a file that no longer exists
+fn forget_state_file(record: &mut Value) {
+    if let Some(spawn) = record.get_mut("spawn").and_then(|v| v.as_object_mut()) {
+        spawn.remove("state");
+    }
+    if let Some(launch) = record.get_mut("launch").and_then(|v| v.as_object_mut()) {
+        launch.remove("storageState");
+    }
+}
+
+/// Send one request to a session, failing with its error message
+fn session_request(flags: &Flags, session: &str, cmd: Value) -> Result<Value, String> {
+    let options = ConnectOptions::from_flags(flags);
+    match send_command(cmd, session, &options) {
+        Ok(resp) if resp.success => Ok(resp.data.unwrap_or(Value::Null)),
+        Ok(resp) => Err(resp.error.unwrap_or_else(|| "Request failed".to_string())),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Reopen `urls` in the target session: the first in its initial tab, the
+/// rest in new tabs, then switch back to the tab that was active
+fn reopen_tabs(flags: &Flags, session: &str, urls: &[String], active: usize) -> Result<(), String> {
+    for (i, url) in urls.iter().enumerate() {
+        let cmd = if i == 0 {
+            json!({ "id": gen_id(), "action": "navigate", "url": url })
+        } else {
+            json!({ "id": gen_id(), "action": "tab_new", "url": url })
+        };
+        session_request(flags, session, cmd)?;
+    }
+    if urls.len() > 1 {
+        let cmd =
+            json!({ "id": gen_id(), "action": "tab_switch", "index": active.min(urls.len() - 1) });
+        session_request(flags, session, cmd)?;
+    }
+    Ok(())
+}
+
+/// Start `to` with the cookies and storage of `from` (and its tabs with `--tabs`)
+fn clone_into(flags: &Flags, from: &str, to: &str, tabs: bool) -> Result<Vec<String>, String> {
+    if from == to {
+        return Err("Source and target session must differ".to_string());
+    }
+    if !read_pid(from).is_some_and(pid_alive) {
+        return Err(format!("Session '{}' is not running", from));
+    }
+    if read_pid(to).is_some_and(pid_alive) {
+        return Err(format!(
+            "Session '{}' is already running; storage state can only be loaded at launch (stop it with: agent-browser session kill {})",
+            to, to
+        ));
+    }
+
+    let urls = if tabs {
+        let data = session_request(flags, from, json!({ "id": gen_id(), "action": "tab_list" }))?;
+        tab_urls(&data)
+    } else {
+        (Vec::new(), 0)
+    };
+
+    // Held until the target has been launched from the state file, so neither
+    // prune nor another CLI starting the target gets in between
+    let lock = lock_session(to)?;
+    if read_pid(to).is_some_and(pid_alive) {
+        return Err(format!("Session '{}' was started meanwhile", to));
+    }
+    let state_path = get_socket_dir().join(format!("{}.clone-state.json", to));
+    let state = state_path.display().to_string();
+    session_request(
+        flags,
+        from,
+        json!({ "id": gen_id(), "action": "state_save", "path": state }),
+    )?;
+
+    let mut target = flags.clone();
+    target.session = to.to_string();
+    target.state = Some(state);
+    let started = prepare_local_daemon(&target, &lock);
+    // The browser has read the state (or failed to start); it holds credentials
+    let _ = fs::remove_file(&state_path);
+    started?;
+    if let Some(mut record) = read_launch_record(to) {
+        forget_state_file(&mut record);
+        if let Some(launch) = record.get("launch") {
+            record_launch(to, "launch", launch.clone());
+        }
+        if let Some(spawn) = record
+            .get("spawn")
+            .and_then(|s| serde_json::from_value(s.clone()).ok())
+        {
+            record_spawn(to, &spawn);
+        }
+    }
+    drop(lock);
+
+    reopen_tabs(flags, to, &urls.0, urls.1)?;
+    Ok(urls.0)
+}
+
+fn clone_session(args: &[String], flags: &Flags) -> i32 {
+    let names: Vec<&String> = args.iter().filter(|a| !a.starts_with('-')).collect();
+    let [from, to] = names.as_slice() else {
+        print_parse_error(
+            &ParseError::MissingArguments {
+                context: "session clone".to_string(),
+                usage: "session clone <from> <to> [--tabs]",
+            },
+            flags.json,
+        );
+        return 1;
+    };
+    let tabs = args.iter().any(|a| a == "--tabs");
+
+    match clone_into(flags, from, to, tabs) {
+        Ok(urls) => {
+            if flags.json {
+                print_json(true, &json!({ "from": from, "to": to, "tabs": urls }));
+            } else {
+                println!(
+                    "{} Cloned session '{}' to '{}'{}",
+                    color::success_indicator(),
+                    from,
+                    to,
+                    if tabs {
+                        format!(
+                            " with {} tab{}",
+                            urls.len(),
+                            if urls.len() == 1 { "" } else { "s" }
+                        )
+                    } else {
+                        String::new()
+                    }
+                );
+            }
+            0
+        }
+        Err(e) => {
+            print_error(&e, flags.json);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> SessionInfo {
+        SessionInfo {
+            name: "work".to_string(),
+            pid: Some(4242),
+            started: Some(1_000_000),
+            socket: Some("/tmp/ab/work.sock".to_string()),
+            ..SessionInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_session_info_apply_status() {
+        let mut info = info();
+        info.apply_status(&json!({
+            "pid": 4242,
+            "idle": 5000,
+            "browser": {
+                "mode": "cdp",
+                "headed": false,
+                "tabs": 2,
+                "url": "https://example.com/",
+                "title": "Example Domain"
+            }
+        }));
+        assert!(info.responsive);
+        assert_eq!(info.idle, Some(5000));
+        assert_eq!(
+            info.browser,
+            Some(BrowserInfo {
+                mode: "cdp".to_string(),
+                headed: false,
+                tabs: 2,
+                url: Some("https://example.com/".to_string()),
+                title: Some("Example Domain".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_session_info_apply_status_without_browser() {
+        let mut info = info();
+        info.apply_status(&json!({ "pid": 4242, "idle": 0, "browser": null }));
+        assert!(info.responsive);
+        assert!(info.browser.is_none());
+    }
+
+    #[test]
+    fn test_session_info_json() {
+        let mut info = info();
+        info.apply_status(&json!({
+            "idle": 10,
+            "browser": { "mode": "local", "headed": true, "tabs": 1, "url": null, "title": null }
+        }));
+        let value = serde_json::to_value(&info).unwrap();
+        assert_eq!(value["name"], "work");
+        assert_eq!(value["pid"], 4242);
+        assert_eq!(value["started"], 1_000_000);
+        assert_eq!(value["socket"], "/tmp/ab/work.sock");
+        assert_eq!(value["responsive"], true);
+        assert_eq!(value["browser"]["headed"], true);
+        assert_eq!(value["browser"]["tabs"], 1);
+    }
+
+    #[test]
+    fn test_session_info_describe() {
+        let now = UNIX_EPOCH + Duration::from_millis(1_000_000 + 90_000);
+        let mut info = info();
+        assert_eq!(info.describe(now), "pid 4242, up 1m 30s, not responding");
+
+        info.apply_status(&json!({ "idle": 3000, "browser": null }));
+        assert_eq!(
+            info.describe(now),
+            "pid 4242, up 1m 30s, idle 3s, no browser"
+        );
+
+        info.apply_status(&json!({
+            "idle": 0,
+            "browser": { "mode": "local", "headed": true, "tabs": 3, "url": "about:blank", "title": "" }
+        }));
+        assert_eq!(
+            info.describe(now),
+            "pid 4242, up 1m 30s, idle 0s, headed, 3 tabs"
+        );
+
+        info.apply_status(&json!({
+            "browser": { "mode": "provider", "headed": false, "tabs": 1, "url": null, "title": null }
+        }));
+        assert_eq!(info.describe(now), "pid 4242, up 1m 30s, provider, 1 tab");
+    }
+
+    #[test]
+    fn test_tab_urls() {
+        let (urls, active) = tab_urls(&json!({
+            "tabs": [
+                { "index": 0, "url": "https://a.example/", "title": "A", "active": false },
+                { "index": 1, "url": "https://b.example/", "title": "B", "active": true }
+            ],
+            "active": 1
+        }));
+        assert_eq!(urls, vec!["https://a.example/", "https://b.example/"]);
+        assert_eq!(active, 1);
+        assert_eq!(tab_urls(&json!({})), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn test_forget_state_file() {
+        let mut record = json!({
+            "spawn": { "headed": true, "state": "/tmp/ab/b.clone-state.json" },
+            "launch": { "action": "launch", "headless": false, "storageState": "/tmp/ab/b.clone-state.json" }
+        });
+        forget_state_file(&mut record);
+        assert_eq!(
+            record,
+            json!({
+                "spawn": { "headed": true },
+                "launch": { "action": "launch", "headless": false }
+            })
+        );
+    }
+
+    #[test]
+    fn test_stale_sessions() {
+        let dir = std::env::temp_dir().join(format!("ab-prune-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // Live daemon: our own pid
+        fs::write(dir.join("live.pid"), std::process::id().to_string()).unwrap();
+        fs::write(dir.join("live.sock"), "").unwrap();
+        // Crashed daemon: pid that can't exist
+        fs::write(dir.join("dead.pid"), "99999999").unwrap();
+        fs::write(dir.join("dead.sock"), "").unwrap();
+        fs::write(dir.join("dead.log"), "crash").unwrap();
+        // State from a clone that never finished, and one still starting
+        fs::write(dir.join("copy.clone-state.json"), "{}").unwrap();
+        fs::write(dir.join("live.clone-state.json"), "{}").unwrap();
+        // Socket with no pid file at all
+        fs::write(dir.join("orphan.sock"), "").unwrap();
+        // Unrelated files are ignored
+        fs::write(dir.join("shell_history"), "").unwrap();
+
+        let stale = stale_sessions(&dir);
+        let names: Vec<&str> = stale.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["copy", "dead", "orphan"]);
+        assert_eq!(stale[0].1, vec![dir.join("copy.clone-state.json")]);
+        assert_eq!(
+            stale[1].1,
+            vec![dir.join("dead.pid"), dir.join("dead.sock")]
+        );
+        assert_eq!(stale[2].1, vec![dir.join("orphan.sock")]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_lock_stale_sessions_skips_locked() {
+        let _guard = crate::connection::tests::EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = std::env::temp_dir().join(format!("ab-prune-lock-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        std::env::set_var("AGENT_BROWSER_SOCKET_DIR", &dir);
+
+        // "starting" has no pid file yet, but its starter holds the lock
+        let starting = lock_session("starting").unwrap();
+        fs::write(dir.join("starting.sock"), "").unwrap();
+        fs::write(dir.join("dead.sock"), "").unwrap();
+
+        let stale = lock_stale_sessions().unwrap();
+        let names: Vec<&str> = stale.iter().map(|(n, _, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["dead"]);
+        // Nobody can start a session while prune holds its lock
+        assert!(try_lock_session("dead").unwrap().is_none());
+
+        drop(stale);
+        drop(starting);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stale_sessions_missing_dir() {
+        assert!(stale_sessions(Path::new("/nonexistent/agent-browser-sockets")).is_empty());
+    }
+}
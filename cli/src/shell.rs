@@ -0,0 +1,266 @@
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::batch::{parse_line, send_and_print, split_line, LineError};
+use crate::color;
+use crate::connection::{get_socket_dir, Client};
+use crate::flags::Flags;
+use crate::launch::prepare_daemon;
+use crate::output::{print_command_help, print_error, print_help, print_parse_error};
+use crate::registry;
+
+/// Meta-commands understood by the shell itself (never sent to the daemon)
+const META_COMMANDS: &[&str] = &[".session", ".help", ".exit", ".quit"];
+
+/// Tab completion for command and subcommand names
+struct ShellHelper;
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, candidates) = complete_line(&line[..pos]);
+        Ok((
+            start,
+            candidates
+                .into_iter()
+                .map(|c| Pair {
+                    display: c.to_string(),
+                    replacement: c.to_string(),
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+/// Completion candidates for the word under the cursor.
+///
+/// The first word completes to command names (and meta-commands), the second to
+/// the subcommands of the first, or to command names after `help`.
+fn complete_line(line: &str) -> (usize, Vec<&'static str>) {
+    let start = line.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+    let partial = &line[start..];
+    let words: Vec<&str> = line[..start].split_whitespace().collect();
+
+    let pool: Vec<&'static str> = match words.as_slice() {
+        [] => registry::command_names()
+            .into_iter()
+            .chain(META_COMMANDS.iter().copied())
+            .collect(),
+        ["help"] => registry::command_names(),
+        [cmd] => registry::next_words(cmd),
+        _ => Vec::new(),
+    };
+
+    let mut matches: Vec<&'static str> = pool
+        .into_iter()
+        .filter(|c| c.starts_with(partial))
+        .collect();
+    matches.sort_unstable();
+    matches.dedup();
+    (start, matches)
+}
+
+fn prompt(session: &str) -> String {
+    format!("{} ", color::cyan(&format!("agent-browser({})>", session)))
+}
+
+/// Run `agent-browser shell`: an interactive REPL bound to a session.
+///
+/// Each line is parsed like CLI arguments (or taken as a raw JSON request) and
+/// sent over one persistent connection. History is kept in the socket
+/// directory. Returns the process exit code.
+pub fn run_shell(flags: &mut Flags) -> i32 {
+    let mut rl: Editor<ShellHelper, FileHistory> = match Editor::new() {
+        Ok(rl) => rl,
+        Err(e) => {
+            print_error(&format!("Failed to start shell: {}", e), flags.json);
+            return 1;
+        }
+    };
+    rl.set_helper(Some(ShellHelper));
+
+    let history_path = get_socket_dir().join("shell_history");
+    let _ = rl.load_history(&history_path);
+
+    if let Err(e) = prepare_daemon(flags) {
+        print_error(&e, flags.json);
+        return 1;
+    }
+
+    if !flags.json {
+        println!(
+            "{}",
+            color::dim(
+                "Type a command (e.g. 'open example.com'), '.help' for help, '.exit' to quit."
+            )
+        );
+    }
+
+    let mut client: Option<Client> = None;
+
+    loop {
+        let line = match rl.readline(&prompt(&flags.session)) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                print_error(&format!("Failed to read input: {}", e), flags.json);
+                break;
+            }
+        };
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let _ = rl.add_history_entry(trimmed);
+
+        if trimmed.starts_with('.') {
+            match run_meta(trimmed, flags, &mut client) {
+                MetaResult::Continue => continue,
+                MetaResult::Exit => break,
+            }
+        }
+
+        if trimmed == "help" || trimmed.starts_with("help ") {
+            match split_line(trimmed).ok().as_deref() {
+                Some([_, cmd, ..]) if print_command_help(cmd) => {}
+                _ => print_help(),
+            }
+            continue;
+        }
+
+        match parse_line(trimmed, flags) {
+            Ok(None) => {}
+            Ok(Some(cmd)) => {
+                // Respawn the daemon if a previous command (e.g. close) stopped it
+                if client.is_none() {
+                    if let Err(e) = prepare_daemon(flags) {
+                        print_error(&e, flags.json);
+                        continue;
+                    }
+                }
+                let closing = cmd.get("action").and_then(|v| v.as_str()) == Some("close");
+                send_and_print(&mut client, &cmd, flags);
+                if closing {
+                    client = None;
+                }
+            }
+            Err(LineError::Parse(e)) => print_parse_error(&e, flags.json),
+            Err(LineError::Invalid(msg)) => print_error(&msg, flags.json),
+        }
+    }
+
+    let _ = rl.save_history(&history_path);
+    0
+}
+
+enum MetaResult {
+    Continue,
+    Exit,
+}
+
+fn run_meta(line: &str, flags: &mut Flags, client: &mut Option<Client>) -> MetaResult {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        [".exit"] | [".quit"] => return MetaResult::Exit,
+        [".session"] => println!("{}", flags.session),
+        [".session", _] if flags.daemon_url.is_some() => print_error(
+            "Can't switch sessions with --daemon-url; the remote daemon serves a single session",
+            flags.json,
+        ),
+        [".session", name] => {
+            let previous = std::mem::replace(&mut flags.session, name.to_string());
+            match prepare_daemon(flags) {
+                Ok(()) => *client = None,
+                Err(e) => {
+                    print_error(&e, flags.json);
+                    flags.session = previous;
+                }
+            }
+        }
+        [".help"] => {
+            println!(
+                r#"Shell commands:
+  <command> [args]     Run any agent-browser command (e.g. snapshot -i)
+  {{"action": ...}}      Send a raw JSON request
+  help [command]       Show help for all commands or one command
+  .session             Show the current session
+  .session <name>      Switch to another session (starting its daemon if needed)
+  .exit, .quit         Leave the shell (Ctrl-D also works)"#
+            );
+        }
+        _ => print_error(
+            &format!("Unknown shell command: {} (try .help)", line),
+            flags.json,
+        ),
+    }
+    MetaResult::Continue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_command_name() {
+        let (start, matches) = complete_line("scr");
+        assert_eq!(start, 0);
+        assert_eq!(
+            matches,
+            vec!["screenshot", "scroll", "scrollinto", "scrollintoview"]
+        );
+    }
+
+    #[test]
+    fn test_complete_meta_command() {
+        let (_, matches) = complete_line(".se");
+        assert_eq!(matches, vec![".session"]);
+    }
+
+    #[test]
+    fn test_complete_subcommand() {
+        let (start, matches) = complete_line("get t");
+        assert_eq!(start, 4);
+        assert_eq!(matches, vec!["text", "title"]);
+    }
+
+    #[test]
+    fn test_complete_all_subcommands() {
+        let (_, matches) = complete_line("network ");
+        assert_eq!(matches, vec!["requests", "route", "unroute"]);
+    }
+
+    #[test]
+    fn test_complete_help_topic() {
+        let (_, matches) = complete_line("help snap");
+        assert_eq!(matches, vec!["snapshot"]);
+    }
+
+    #[test]
+    fn test_complete_nothing_after_arguments() {
+        let (_, matches) = complete_line("click @e1 ");
+        assert!(matches.is_empty());
+    }
+}
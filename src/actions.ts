@@ -488,6 +488,7 @@ async function handleNavigate(
 
   await page.goto(command.url, {
     waitUntil: command.waitUntil ?? 'load',
+    timeout: command.timeout,
   });
 
   return successResponse(command.id, {
@@ -624,9 +625,27 @@ async function handleEvaluate(
   const page = browser.getPage();
 
   // Evaluate the script directly as a string expression
-  const result = await page.evaluate(command.script);
+  const evaluation = page.evaluate(command.script);
+  if (!command.timeout) {
+    return successResponse(command.id, { result: await evaluation });
+  }
 
-  return successResponse(command.id, { result });
+  // page.evaluate has no timeout option, so give up on the script after command.timeout
+  let timer: ReturnType<typeof setTimeout> | undefined;
+  const expired = new Promise<never>((_, reject) => {
+    timer = setTimeout(
+      () => reject(new Error(`Evaluation timed out after ${command.timeout}ms`)),
+      command.timeout
+    );
+  });
+  // A script that fails after the timeout has nobody left to report to
+  evaluation.catch(() => {});
+  try {
+    const result = await Promise.race([evaluation, expired]);
+    return successResponse(command.id, { result });
+  } finally {
+    clearTimeout(timer);
+  }
 }
 
 async function handleWait(command: WaitCommand, browser: BrowserManager): Promise<Response> {
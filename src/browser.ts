@@ -64,6 +64,15 @@ interface PageError {
   timestamp: number;
 }
 
+/**
+ * Live page event delivered to `events` subscribers
+ */
+export type BrowserEvent =
+  | { kind: 'console'; type: string; text: string; timestamp: number }
+  | { kind: 'error'; message: string; timestamp: number }
+  | { kind: 'request'; method: string; url: string; resourceType: string; timestamp: number }
+  | { kind: 'dialog'; type: string; message: string; timestamp: number };
+
 /**
  * Manages the Playwright browser lifecycle with multiple tabs/windows
  */
@@ -71,6 +80,7 @@ export class BrowserManager {
   private browser: Browser | null = null;
   private cdpEndpoint: string | null = null; // stores port number or full URL
   private isPersistentContext: boolean = false;
+  private headed: boolean = false;
   private browserbaseSessionId: string | null = null;
   private browserbaseApiKey: string | null = null;
   private browserUseSessionId: string | null = null;
@@ -86,6 +96,7 @@ export class BrowserManager {
   private routes: Map<string, (route: Route) => Promise<void>> = new Map();
   private consoleMessages: ConsoleMessage[] = [];
   private pageErrors: PageError[] = [];
+  private eventListeners: Set<(event: BrowserEvent) => void> = new Set();
   private isRecordingHar: boolean = false;
   private refMap: RefMap = {};
   private lastSnapshot: string = '';
@@ -111,6 +122,19 @@ export class BrowserManager {
     return this.browser !== null || this.isPersistentContext;
   }
 
+  /**
+   * How the current browser was obtained and whether it has a visible window
+   */
+  getLaunchMode(): { mode: 'local' | 'cdp' | 'provider'; headed: boolean } {
+    const mode =
+      this.browserbaseSessionId || this.browserUseSessionId || this.kernelSessionId
+        ? 'provider'
+        : this.cdpEndpoint !== null
+          ? 'cdp'
+          : 'local';
+    return { mode, headed: this.headed };
+  }
+
   /**
    * Get enhanced snapshot with refs and cache the ref map
    */
@@ -507,6 +531,45 @@ export class BrowserManager {
     this.pageErrors = [];
   }
 
+  /**
+   * Subscribe to live console, error, request and dialog events.
+   * Returns a function that removes the listener.
+   */
+  onEvent(listener: (event: BrowserEvent) => void): () => void {
+    this.eventListeners.add(listener);
+    return () => {
+      this.eventListeners.delete(listener);
+    };
+  }
+
+  /**
+   * Get buffered console messages, page errors and tracked requests as events, oldest first
+   */
+  getBufferedEvents(): BrowserEvent[] {
+    const events: BrowserEvent[] = [
+      ...this.consoleMessages.map((m) => ({ kind: 'console' as const, ...m })),
+      ...this.pageErrors.map((e) => ({ kind: 'error' as const, ...e })),
+      ...this.trackedRequests.map((r) => ({
+        kind: 'request' as const,
+        method: r.method,
+        url: r.url,
+        resourceType: r.resourceType,
+        timestamp: r.timestamp,
+      })),
+    ];
+    return events.sort((a, b) => a.timestamp - b.timestamp);
+  }
+
+  private emitEvent(event: BrowserEvent): void {
+    for (const listener of this.eventListeners) {
+      try {
+        listener(event);
+      } catch {
+        // Ignore subscriber errors (e.g. client disconnected)
+      }
+    }
+  }
+
   /**
    * Start HAR recording
    */
@@ -1088,6 +1151,9 @@ export class BrowserManager {
       throw new Error('allowFileAccess is only supported in Chromium');
     }
 
+    // Extensions always run in a visible window
+    this.headed = hasExtensions || options.headless === false;
+
     const launcher =
       browserType === 'firefox' ? firefox : browserType === 'webkit' ? webkit : chromium;
     const viewport = options.viewport ?? { width: 1280, height: 720 };
@@ -1266,20 +1332,49 @@ export class BrowserManager {
    */
   private setupPageTracking(page: Page): void {
     page.on('console', (msg) => {
-      this.consoleMessages.push({
+      const message = {
         type: msg.type(),
         text: msg.text(),
         timestamp: Date.now(),
-      });
+      };
+      this.consoleMessages.push(message);
+      this.emitEvent({ kind: 'console', ...message });
     });
 
     page.on('pageerror', (error) => {
-      this.pageErrors.push({
+      const pageError = {
         message: error.message,
         timestamp: Date.now(),
+      };
+      this.pageErrors.push(pageError);
+      this.emitEvent({ kind: 'error', ...pageError });
+    });
+
+    page.on('request', (request: Request) => {
+      if (this.eventListeners.size === 0) return;
+      this.emitEvent({
+        kind: 'request',
+        method: request.method(),
+        url: request.url(),
+        resourceType: request.resourceType(),
+        timestamp: Date.now(),
       });
     });
 
+    page.on('dialog', async (dialog: Dialog) => {
+      this.emitEvent({
+        kind: 'dialog',
+        type: dialog.type(),
+        message: dialog.message(),
+        timestamp: Date.now(),
+      });
+      // Any dialog listener disables Playwright's auto-dismiss, so keep that
+      // default unless an explicit handler was installed via setDialogHandler
+      if (page.listenerCount('dialog') === 1) {
+        await dialog.dismiss().catch(() => {});
+      }
+    });
+
     page.on('close', () => {
       const index = this.pages.indexOf(page);
       if (index !== -1) {
@@ -1920,6 +2015,7 @@ export class BrowserManager {
     this.kernelSessionId = null;
     this.kernelApiKey = null;
     this.isPersistentContext = false;
+    this.headed = false;
     this.activePageIndex = 0;
     this.refMap = {};
     this.lastSnapshot = '';
@@ -2,12 +2,15 @@ import * as net from 'net';
 import * as fs from 'fs';
 import * as path from 'path';
 import * as os from 'os';
+import * as crypto from 'crypto';
+import { fileURLToPath } from 'url';
 import { BrowserManager } from './browser.js';
 import { IOSManager } from './ios-manager.js';
-import { parseCommand, serializeResponse, errorResponse } from './protocol.js';
+import { parseCommand, serializeResponse, errorResponse, successResponse } from './protocol.js';
 import { executeCommand } from './actions.js';
 import { executeIOSCommand } from './ios-actions.js';
 import { StreamServer } from './stream-server.js';
+import type { StealthOptions } from './types.js';
 
 // Manager type - either desktop browser or iOS
 type Manager = BrowserManager | IOSManager;
@@ -24,6 +27,28 @@ let streamServer: StreamServer | null = null;
 // Default stream port (can be overridden with AGENT_BROWSER_STREAM_PORT)
 const DEFAULT_STREAM_PORT = 9223;
 
+/**
+ * Version of this daemon build, read from package.json. The CLI compares it
+ * with its own version in the hello handshake.
+ */
+export const DAEMON_VERSION = readPackageVersion();
+
+function readPackageVersion(): string {
+  try {
+    const pkgPath = path.join(path.dirname(fileURLToPath(import.meta.url)), '..', 'package.json');
+    const pkg = JSON.parse(fs.readFileSync(pkgPath, 'utf8')) as { version?: unknown };
+    return typeof pkg.version === 'string' ? pkg.version : 'unknown';
+  } catch {
+    return 'unknown';
+  }
+}
+
+/**
+ * Requests that don't count as activity for the idle timeout (status polling
+ * such as `session list` must not keep a forgotten daemon alive)
+ */
+const PASSIVE_ACTIONS = new Set(['hello', 'daemon_status', 'session_list']);
+
 /**
  * Set the current session
  */
@@ -38,6 +63,41 @@ export function getSession(): string {
   return currentSession;
 }
 
+/**
+ * Summarize the browser a manager is driving for daemon_status: how it was
+ * obtained, the active tab and the tab count. Returns null before launch.
+ */
+async function describeBrowser(manager: Manager): Promise<{
+  mode: 'local' | 'cdp' | 'provider' | 'ios';
+  headed: boolean;
+  tabs: number;
+  url: string | null;
+  title: string | null;
+} | null> {
+  if (!manager.isLaunched()) {
+    return null;
+  }
+  if (manager instanceof IOSManager) {
+    return {
+      mode: 'ios',
+      headed: true,
+      tabs: 1,
+      url: await manager.getUrl().catch(() => null),
+      title: await manager.getTitle().catch(() => null),
+    };
+  }
+  const { mode, headed } = manager.getLaunchMode();
+  const pages = manager.getPages();
+  const page = pages[manager.getActiveIndex()];
+  return {
+    mode,
+    headed,
+    tabs: pages.length,
+    url: page ? page.url() : null,
+    title: page ? await page.title().catch(() => null) : null,
+  };
+}
+
 /**
  * Get port number for TCP mode (Windows)
  * Uses a hash of the session name to get a consistent port
@@ -126,6 +186,43 @@ export function isDaemonRunning(session?: string): boolean {
   }
 }
 
+/**
+ * List sessions with a live daemon on this machine
+ */
+export function listSessions(): string[] {
+  const socketDir = getSocketDir();
+  if (!fs.existsSync(socketDir)) return [];
+  return fs
+    .readdirSync(socketDir)
+    .filter((name) => name.endsWith('.pid'))
+    .map((name) => name.slice(0, -'.pid'.length))
+    .filter((sess) => sess.length > 0 && isDaemonRunning(sess));
+}
+
+/**
+ * Parse AGENT_BROWSER_LISTEN ("host:port" or "port") for the remote TCP listener.
+ * A bare port binds the loopback interface; other hosts must be named.
+ */
+function parseListenAddress(value: string): { host: string; port: number } {
+  const idx = value.lastIndexOf(':');
+  const host = idx >= 0 ? value.slice(0, idx) : '127.0.0.1';
+  const port = parseInt(idx >= 0 ? value.slice(idx + 1) : value, 10);
+  if (!Number.isInteger(port) || port <= 0 || port > 65535) {
+    throw new Error(`Invalid AGENT_BROWSER_LISTEN address: ${value}`);
+  }
+  return { host: host || '127.0.0.1', port };
+}
+
+/**
+ * Compare a client token with the expected one in constant time
+ */
+function tokenMatches(given: unknown, expected: string): boolean {
+  if (typeof given !== 'string') return false;
+  const a = Buffer.from(given);
+  const b = Buffer.from(expected);
+  return a.length === b.length && crypto.timingSafeEqual(a, b);
+}
+
 /**
  * Get connection info for the current session
  * Returns { type: 'unix', path: string } or { type: 'tcp', port: number }
@@ -178,10 +275,10 @@ export async function startDaemon(options?: {
   streamPort?: number;
   provider?: string;
 }): Promise<void> {
-  // Ensure socket directory exists
+  // Ensure socket directory exists, private to this user
   const socketDir = getSocketDir();
   if (!fs.existsSync(socketDir)) {
-    fs.mkdirSync(socketDir, { recursive: true });
+    fs.mkdirSync(socketDir, { recursive: true, mode: 0o700 });
   }
 
   // Clean up any stale socket
@@ -195,6 +292,13 @@ export async function startDaemon(options?: {
   const manager: Manager = isIOS ? new IOSManager() : new BrowserManager();
   let shuttingDown = false;
 
+  // Idle shutdown: the CLI passes --idle-timeout as AGENT_BROWSER_IDLE_TIMEOUT_MS
+  const idleTimeoutMs = parseInt(process.env.AGENT_BROWSER_IDLE_TIMEOUT_MS ?? '', 10) || 0;
+  let lastActivity = Date.now();
+  // Commands still executing and `events --follow` subscribers keep the daemon busy
+  let activeCommands = 0;
+  let followers = 0;
+
   // Start stream server if port is specified (or use default if env var is set)
   // Note: Stream server only works with BrowserManager (desktop), not iOS
   const streamPort =
@@ -212,9 +316,17 @@ export async function startDaemon(options?: {
     fs.writeFileSync(streamPortFile, streamPort.toString());
   }
 
-  const server = net.createServer((socket) => {
+  // Optional TCP listener for remote CLIs (`--daemon-url`), guarded by a shared token
+  const listenAddress = process.env.AGENT_BROWSER_LISTEN;
+  const remoteToken = process.env.AGENT_BROWSER_DAEMON_TOKEN;
+  if (listenAddress && !remoteToken) {
+    throw new Error('AGENT_BROWSER_LISTEN requires AGENT_BROWSER_DAEMON_TOKEN to be set');
+  }
+
+  const handleConnection = (socket: net.Socket, requireAuth: boolean) => {
     let buffer = '';
     let httpChecked = false;
+    let authenticated = !requireAuth;
 
     socket.on('data', async (data) => {
       buffer += data.toString();
@@ -239,6 +351,25 @@ export async function startDaemon(options?: {
 
         if (!line.trim()) continue;
 
+        // Remote clients must send {"token": "..."} before any command
+        if (!authenticated) {
+          let token: unknown;
+          try {
+            token = JSON.parse(line).token;
+          } catch {
+            token = undefined;
+          }
+          if (remoteToken && tokenMatches(token, remoteToken)) {
+            authenticated = true;
+            socket.write(
+              serializeResponse(successResponse('auth', { session: currentSession })) + '\n'
+            );
+            continue;
+          }
+          socket.end(serializeResponse(errorResponse('auth', 'Invalid token')) + '\n');
+          return;
+        }
+
         try {
           const parseResult = parseCommand(line);
 
@@ -268,6 +399,53 @@ export async function startDaemon(options?: {
             continue;
           }
 
+          // Handle session_list specially - lists daemons on this host (for remote CLIs)
+          if (parseResult.command.action === 'session_list') {
+            socket.write(
+              serializeResponse(
+                successResponse(parseResult.command.id, { sessions: listSessions() })
+              ) + '\n'
+            );
+            continue;
+          }
+
+          if (!PASSIVE_ACTIONS.has(parseResult.command.action)) {
+            lastActivity = Date.now();
+          }
+
+          // Handle hello specially - version handshake, answered before anything else
+          if (parseResult.command.action === 'hello') {
+            socket.write(
+              serializeResponse(
+                successResponse(parseResult.command.id, {
+                  version: DAEMON_VERSION,
+                  session: currentSession,
+                })
+              ) + '\n'
+            );
+            continue;
+          }
+
+          // Handle daemon_status specially - reports on the daemon without launching a browser
+          if (parseResult.command.action === 'daemon_status') {
+            socket.write(
+              serializeResponse(
+                successResponse(parseResult.command.id, {
+                  pid: process.pid,
+                  session: currentSession,
+                  version: DAEMON_VERSION,
+                  idle: Date.now() - lastActivity,
+                  idleTimeout: idleTimeoutMs > 0 ? idleTimeoutMs : null,
+                  uptime: Math.round(process.uptime() * 1000),
+                  launched: manager.isLaunched(),
+                  provider: provider ?? null,
+                  browser: await describeBrowser(manager),
+                })
+              ) + '\n'
+            );
+            continue;
+          }
+
           // Auto-launch if not already launched and this isn't a launch/close command
           if (
             !manager.isLaunched() &&
@@ -312,6 +490,10 @@ export async function startDaemon(options?: {
 
               const ignoreHTTPSErrors = process.env.AGENT_BROWSER_IGNORE_HTTPS_ERRORS === '1';
               const allowFileAccess = process.env.AGENT_BROWSER_ALLOW_FILE_ACCESS === '1';
+              const stealth = process.env.AGENT_BROWSER_STEALTH === '1';
+              const stealthOptions = process.env.AGENT_BROWSER_STEALTH_OPTIONS
+                ? (JSON.parse(process.env.AGENT_BROWSER_STEALTH_OPTIONS) as StealthOptions)
+                : undefined;
               await manager.launch({
                 id: 'auto',
                 action: 'launch' as const,
@@ -325,8 +507,43 @@ export async function startDaemon(options?: {
                 proxy,
                 ignoreHTTPSErrors: ignoreHTTPSErrors,
                 allowFileAccess: allowFileAccess,
+                stealth,
+                stealthOptions,
+              });
+            }
+          }
+
+          // Handle events specially - reply with buffered events and, when following,
+          // keep pushing new events on this connection until the client disconnects
+          if (parseResult.command.action === 'events') {
+            const command = parseResult.command;
+            if (!(manager instanceof BrowserManager)) {
+              socket.write(
+                serializeResponse(errorResponse(command.id, 'Events are not supported on iOS')) +
+                  '\n'
+              );
+              continue;
+            }
+            const kinds = new Set(command.kinds ?? ['console', 'error', 'request', 'dialog']);
+            const buffered = manager.getBufferedEvents().filter((e) => kinds.has(e.kind));
+            socket.write(
+              serializeResponse(successResponse(command.id, { kinds: [...kinds], buffered })) +
+                '\n'
+            );
+            if (command.follow) {
+              const unsubscribe = manager.onEvent((event) => {
+                if (kinds.has(event.kind) && !socket.destroyed) {
+                  socket.write(JSON.stringify({ event }) + '\n');
+                }
+              });
+              followers++;
+              socket.once('close', () => {
+                unsubscribe();
+                followers--;
+                lastActivity = Date.now();
               });
             }
+            continue;
           }
 
           // Handle close command specially - shuts down daemon
@@ -341,6 +558,7 @@ export async function startDaemon(options?: {
               shuttingDown = true;
               setTimeout(() => {
                 server.close();
+                remoteServer?.close();
                 cleanupSocket();
                 process.exit(0);
               }, 100);
@@ -349,10 +567,17 @@ export async function startDaemon(options?: {
           }
 
           // Execute command with appropriate handler
-          const response =
-            isIOS && manager instanceof IOSManager
-              ? await executeIOSCommand(parseResult.command, manager)
-              : await executeCommand(parseResult.command, manager as BrowserManager);
+          activeCommands++;
+          let response;
+          try {
+            response =
+              isIOS && manager instanceof IOSManager
+                ? await executeIOSCommand(parseResult.command, manager)
+                : await executeCommand(parseResult.command, manager as BrowserManager);
+          } finally {
+            activeCommands--;
+            lastActivity = Date.now();
+          }
           socket.write(serializeResponse(response) + '\n');
         } catch (err) {
           const message = err instanceof Error ? err.message : String(err);
@@ -364,12 +589,15 @@ export async function startDaemon(options?: {
     socket.on('error', () => {
       // Client disconnected, ignore
     });
-  });
+  };
+
+  const server = net.createServer((socket) => handleConnection(socket, false));
+  let remoteServer: net.Server | null = null;
 
   const pidFile = getPidFile();
 
   // Write PID file before listening
-  fs.writeFileSync(pidFile, process.pid.toString());
+  fs.writeFileSync(pidFile, process.pid.toString(), { mode: 0o600 });
 
   if (isWindows) {
     // Windows: use TCP socket on localhost
@@ -383,7 +611,8 @@ export async function startDaemon(options?: {
     // Unix: use Unix domain socket
     const socketPath = getSocketPath();
     server.listen(socketPath, () => {
-      // Daemon is ready
+      // Daemon is ready; only the owner may connect
+      fs.chmodSync(socketPath, 0o600);
     });
   }
 
@@ -393,6 +622,17 @@ export async function startDaemon(options?: {
     process.exit(1);
   });
 
+  if (listenAddress) {
+    const { host, port } = parseListenAddress(listenAddress);
+    remoteServer = net.createServer((socket) => handleConnection(socket, true));
+    remoteServer.on('error', (err) => {
+      console.error('Remote listener error:', err);
+      cleanupSocket();
+      process.exit(1);
+    });
+    remoteServer.listen(port, host);
+  }
+
   // Handle shutdown signals
   const shutdown = async () => {
     if (shuttingDown) return;
@@ -413,6 +653,7 @@ export async function startDaemon(options?: {
 
     await manager.close();
     server.close();
+    remoteServer?.close();
     cleanupSocket();
     process.exit(0);
   };
@@ -421,6 +662,24 @@ export async function startDaemon(options?: {
   process.on('SIGTERM', shutdown);
   process.on('SIGHUP', shutdown);
 
+  if (idleTimeoutMs > 0) {
+    const idleTimer = setInterval(
+      () => {
+        if (activeCommands > 0 || followers > 0) {
+          lastActivity = Date.now();
+          return;
+        }
+        if (Date.now() - lastActivity >= idleTimeoutMs) {
+          clearInterval(idleTimer);
+          console.log(`No commands for ${Math.round(idleTimeoutMs / 1000)}s, shutting down`);
+          void shutdown();
+        }
+      },
+      Math.min(idleTimeoutMs, 10_000)
+    );
+    idleTimer.unref();
+  }
+
   // Handle unexpected errors - always cleanup
   process.on('uncaughtException', (err) => {
     console.error('Uncaught exception:', err);
@@ -31,6 +31,23 @@ describe('parseCommand', () => {
       }
     });
 
+    it('should keep the timeout on navigate and evaluate', () => {
+      const navigate = parseCommand(
+        cmd({ id: '1', action: 'navigate', url: 'https://example.com', timeout: 60000 })
+      );
+      expect(navigate.success && navigate.command.action === 'navigate').toBe(true);
+      if (navigate.success && navigate.command.action === 'navigate') {
+        expect(navigate.command.timeout).toBe(60000);
+      }
+      const evaluate = parseCommand(
+        cmd({ id: '2', action: 'evaluate', script: 'document.title', timeout: 500 })
+      );
+      expect(evaluate.success && evaluate.command.action === 'evaluate').toBe(true);
+      if (evaluate.success && evaluate.command.action === 'evaluate') {
+        expect(evaluate.command.timeout).toBe(500);
+      }
+    });
+
     it('should reject navigate without url', () => {
       const result = parseCommand(cmd({ id: '1', action: 'navigate' }));
       expect(result.success).toBe(false);
@@ -582,6 +599,24 @@ describe('parseCommand', () => {
       const result = parseCommand(cmd({ id: '1', action: 'launch', ignoreHTTPSErrors: 'true' }));
       expect(result.success).toBe(false);
     });
+
+    it('should keep stealth and stealthOptions on launch', () => {
+      const result = parseCommand(
+        cmd({ id: '1', action: 'launch', stealth: true, stealthOptions: { canvasNoise: false } })
+      );
+      expect(result.success).toBe(true);
+      if (result.success) {
+        expect(result.command.stealth).toBe(true);
+        expect(result.command.stealthOptions).toEqual({ canvasNoise: false });
+      }
+    });
+
+    it('should reject unknown stealthOptions keys', () => {
+      const result = parseCommand(
+        cmd({ id: '1', action: 'launch', stealth: true, stealthOptions: { canvasNoize: false } })
+      );
+      expect(result.success).toBe(false);
+    });
   });
 
   describe('mouse actions', () => {
@@ -1108,6 +1143,16 @@ describe('parseCommand', () => {
     });
   });
 
+  describe('handshake', () => {
+    it('should parse hello with the client version', () => {
+      const result = parseCommand(cmd({ id: 'hello', action: 'hello', version: '0.9.2' }));
+      expect(result.success).toBe(true);
+      if (result.success && result.command.action === 'hello') {
+        expect(result.command.version).toBe('0.9.2');
+      }
+    });
+  });
+
   describe('invalid commands', () => {
     it('should reject unknown action', () => {
       const result = parseCommand(cmd({ id: '1', action: 'unknown' }));
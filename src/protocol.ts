@@ -7,10 +7,41 @@ const baseCommandSchema = z.object({
   action: z.string(),
 });
 
+// Stealth settings; unknown keys are rejected so typos don't pass silently
+const stealthOptionsSchema = z
+  .object({
+    webdriver: z.boolean(),
+    navigator: z.boolean(),
+    navigatorLanguages: z.boolean(),
+    navigatorPlatform: z.boolean(),
+    navigatorHardwareConcurrency: z.boolean(),
+    navigatorDeviceMemory: z.boolean(),
+    screenWidth: z.boolean(),
+    screenHeight: z.boolean(),
+    pixelRatio: z.boolean(),
+    colorDepth: z.boolean(),
+    touchSupport: z.boolean(),
+    chromeRuntime: z.boolean(),
+    permissions: z.boolean(),
+    windowFrame: z.boolean(),
+    doNotTrack: z.boolean(),
+    plugins: z.boolean(),
+    mediaDevices: z.boolean(),
+    customUserAgent: z.string(),
+    canvasNoise: z.boolean(),
+    webglNoise: z.boolean(),
+    audioContextNoise: z.boolean(),
+    behaviorRandomization: z.boolean(),
+  })
+  .partial()
+  .strict();
+
 // Individual action schemas
 const launchSchema = baseCommandSchema.extend({
   action: z.literal('launch'),
   headless: z.boolean().optional(),
+  stealth: z.boolean().optional(),
+  stealthOptions: stealthOptionsSchema.optional(),
   viewport: z
     .object({
       width: z.number().positive(),
@@ -55,6 +86,7 @@ const navigateSchema = baseCommandSchema.extend({
   url: z.string().min(1),
   waitUntil: z.enum(['load', 'domcontentloaded', 'networkidle']).optional(),
   headers: z.record(z.string()).optional(),
+  timeout: z.number().positive().optional(),
 });
 
 const clickSchema = baseCommandSchema.extend({
@@ -696,6 +728,25 @@ const swipeSchema = baseCommandSchema.extend({
   distance: z.number().positive().optional(),
 });
 
+const sessionListSchema = baseCommandSchema.extend({
+  action: z.literal('session_list'),
+});
+
+const daemonStatusSchema = baseCommandSchema.extend({
+  action: z.literal('daemon_status'),
+});
+
+const helloSchema = baseCommandSchema.extend({
+  action: z.literal('hello'),
+  version: z.string().optional(),
+});
+
+const eventsSchema = baseCommandSchema.extend({
+  action: z.literal('events'),
+  kinds: z.array(z.enum(['console', 'error', 'request', 'dialog'])).optional(),
+  follow: z.boolean().optional(),
+});
+
 const deviceListSchema = baseCommandSchema.extend({
   action: z.literal('device_list'),
 });
@@ -727,6 +778,7 @@ const evaluateSchema = baseCommandSchema.extend({
   action: z.literal('evaluate'),
   script: z.string().min(1),
   args: z.array(z.unknown()).optional(),
+  timeout: z.number().positive().optional(),
 });
 
 const waitSchema = baseCommandSchema.extend({
@@ -922,6 +974,10 @@ const commandSchema = z.discriminatedUnion('action', [
   inputTouchSchema,
   swipeSchema,
   deviceListSchema,
+  eventsSchema,
+  sessionListSchema,
+  daemonStatusSchema,
+  helloSchema,
 ]);
 
 // Parse result type
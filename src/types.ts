@@ -67,6 +67,7 @@ export interface NavigateCommand extends BaseCommand {
   url: string;
   waitUntil?: 'load' | 'domcontentloaded' | 'networkidle';
   headers?: Record<string, string>;
+  timeout?: number;
 }
 
 export interface ClickCommand extends BaseCommand {
@@ -566,6 +567,29 @@ export interface DeviceListCommand extends BaseCommand {
   action: 'device_list';
 }
 
+export interface DaemonStatusCommand extends BaseCommand {
+  action: 'daemon_status';
+}
+
+// Version handshake sent by the CLI when it opens a connection
+export interface HelloCommand extends BaseCommand {
+  action: 'hello';
+  version?: string;
+}
+
+export interface SessionListCommand extends BaseCommand {
+  action: 'session_list';
+}
+
+// Event streaming - reply with buffered events, then (with follow) push new ones
+export type BrowserEventKind = 'console' | 'error' | 'request' | 'dialog';
+
+export interface EventsCommand extends BaseCommand {
+  action: 'events';
+  kinds?: BrowserEventKind[];
+  follow?: boolean;
+}
+
 // Video recording (Playwright native - requires launch-time setup)
 export interface VideoStartCommand extends BaseCommand {
   action: 'video_start';
@@ -789,6 +813,7 @@ export interface EvaluateCommand extends BaseCommand {
   action: 'evaluate';
   script: string;
   args?: unknown[];
+  timeout?: number;
 }
 
 export interface WaitCommand extends BaseCommand {
@@ -978,7 +1003,11 @@ export type Command =
   | InputKeyboardCommand
   | InputTouchCommand
   | SwipeCommand
-  | DeviceListCommand;
+  | DeviceListCommand
+  | EventsCommand
+  | SessionListCommand
+  | DaemonStatusCommand
+  | HelloCommand;
 
 // Response types
 export interface SuccessResponse<T = unknown> {